@@ -1,7 +1,7 @@
 use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
 use trade_aggregation::{
-    aggregate_all_trades, candle_components::*, load_trades_from_csv, CandleComponent,
-    GenericAggregator, ModularCandle, TimeRule, TimestampResolution, Trade, M1,
+    aggregate_all_trades, candle_components::*, load_trades_from_csv, GenericAggregator, TimeRule,
+    TimestampResolution, Trade, M1,
 };
 use trade_aggregation_derive::Candle;
 
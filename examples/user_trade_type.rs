@@ -6,10 +6,8 @@
 //! the `MyCandle` struct definition for this attribute and uses it to define the generic
 //! trait `ModularCandle<T>`.
 
-//! When deriving the 'Candle' macro, make sure the following things are in scope:
-//! - Trade
-//! - ModularCandle
-//! - CandleComponent
+//! The 'Candle' macro refers to the traits of this crate by their absolute paths,
+//! so they don't need to be in scope when deriving it, only the `input` type does.
 
 use std::marker::PhantomData;
 
@@ -50,7 +48,7 @@ impl TakerTrade for Tick {
     #[inline(always)]
     fn size(&self) -> f64 {
         match self.side {
-            Side::Bid => self.size as f64 * -1.0,
+            Side::Bid => -(self.size as f64),
             Side::Ask => self.size as f64,
         }
     }
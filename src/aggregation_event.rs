@@ -0,0 +1,24 @@
+/// A finished candle together with the information about why and where it was finished,
+/// allowing the candle boundaries to be audited and reproduced.
+/// Produced by `GenericAggregator::update_with_event`.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AggregationEvent<C, Reason> {
+    /// The finished candle
+    pub candle: C,
+
+    /// The timestamp of the trade that triggered the `AggregationRule`
+    pub trigger_timestamp: i64,
+
+    /// The zero based sequence number of the triggering trade,
+    /// counting all trades the aggregator has been updated with
+    pub trigger_sequence: u64,
+
+    /// If true, the triggering trade is included in the finished candle
+    /// as well as in the next one, see `include_trade_that_triggered_rule` of `GenericAggregator::new`.
+    /// Otherwise it is only part of the next candle.
+    pub trigger_included: bool,
+
+    /// The rule specific reason, see `TriggerReason`
+    pub reason: Reason,
+}
@@ -0,0 +1,182 @@
+use super::adaptive_threshold::AdaptiveThreshold;
+use crate::{AggregationRule, Error, ModularCandle, Result, TakerTrade, TriggerReason};
+
+/// Creates candles once the price changed by a relative price delta, like the `RelativePriceRule`,
+/// but with a delta that adapts to the volatility to target a number of candles per day.
+/// After every candle, the realized variance of its log returns between trades and its duration are added to EWMAs.
+/// As the price needs about h^2 / sigma^2 seconds to move by h, given a variance of sigma^2 per second,
+/// the delta is recomputed as the square root of the ratio of the two EWMAs,
+/// multiplied by the targeted candle duration in seconds.
+/// As a flat market has no variance, the delta never falls below a configured minimum.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AdaptiveRelativePriceRule {
+    init: bool,
+    init_price: f64,
+    // The price of the previous trade
+    prev_price: f64,
+    // The most recently observed relative price change
+    price_delta: f64,
+    // The sum of the squared log returns of the current candle
+    realized_variance: f64,
+    threshold_fraction: f64,
+    min_threshold_fraction: f64,
+    adaptive: AdaptiveThreshold,
+}
+
+impl AdaptiveRelativePriceRule {
+    /// Create a new instance of the `AdaptiveRelativePriceRule`
+    ///
+    /// # Arguments:
+    /// `initial_threshold_fraction`: The relative distance ((`p_t` - `p_i`) / `p_i`) of the first candle
+    /// `min_threshold_fraction`: The positive lower bound of the relative distance,
+    ///     which must not exceed `initial_threshold_fraction`
+    /// `candles_per_day`: The number of candles per day the relative distance targets
+    /// `ewma_span`: The number of past candles the variance and durations are smoothed over
+    ///
+    pub fn new(
+        initial_threshold_fraction: f64,
+        min_threshold_fraction: f64,
+        candles_per_day: f64,
+        ewma_span: usize,
+    ) -> Result<Self> {
+        if !min_threshold_fraction.is_finite()
+            || min_threshold_fraction <= 0.0
+            || !initial_threshold_fraction.is_finite()
+            || initial_threshold_fraction < min_threshold_fraction
+        {
+            return Err(Error::InvalidParam);
+        }
+        Ok(Self {
+            init: true,
+            init_price: 0.0,
+            prev_price: 0.0,
+            price_delta: 0.0,
+            realized_variance: 0.0,
+            threshold_fraction: initial_threshold_fraction,
+            min_threshold_fraction,
+            adaptive: AdaptiveThreshold::new(
+                initial_threshold_fraction * initial_threshold_fraction,
+                candles_per_day,
+                ewma_span,
+            )?,
+        })
+    }
+
+    /// The relative price delta of the current candle
+    pub fn current_threshold(&self) -> f64 {
+        self.threshold_fraction
+    }
+
+    fn finish<T: TakerTrade>(&mut self, trade: &T) {
+        self.adaptive.finish(trade, self.realized_variance);
+        self.threshold_fraction = self
+            .adaptive
+            .expected_activity()
+            .sqrt()
+            .max(self.min_threshold_fraction);
+        self.realized_variance = 0.0;
+        self.init_price = trade.price();
+    }
+}
+
+impl<C, T> AggregationRule<C, T> for AdaptiveRelativePriceRule
+where
+    C: ModularCandle<T>,
+    T: TakerTrade,
+{
+    fn should_trigger(&mut self, trade: &T, _candle: &C) -> bool {
+        let price = trade.price();
+        if self.init {
+            self.init = false;
+            self.adaptive.start(trade);
+            self.init_price = price;
+            self.prev_price = price;
+            return false;
+        }
+
+        self.realized_variance += (price / self.prev_price).ln().powi(2);
+        self.prev_price = price;
+        self.price_delta = (price - self.init_price).abs() / self.init_price;
+
+        if self.price_delta >= self.threshold_fraction {
+            self.finish(trade);
+            return true;
+        }
+        false
+    }
+
+    fn reset(&mut self, trade: &T) {
+        if self.init {
+            self.init = false;
+            self.adaptive.start(trade);
+            self.prev_price = trade.price();
+        }
+        self.finish(trade);
+    }
+}
+
+impl TriggerReason for AdaptiveRelativePriceRule {
+    /// The relative price change since the last candle
+    type Reason = f64;
+
+    fn trigger_reason(&self) -> f64 {
+        self.price_delta
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{plot::OhlcCandle, Aggregator, GenericAggregator, Trade};
+
+    #[test]
+    fn adaptive_relative_price_rule() {
+        assert!(AdaptiveRelativePriceRule::new(0.01, 0.0, 1440.0, 10).is_err());
+        assert!(AdaptiveRelativePriceRule::new(0.001, 0.01, 1440.0, 10).is_err());
+        assert!(AdaptiveRelativePriceRule::new(0.01, 0.0001, -1.0, 10).is_err());
+        assert!(AdaptiveRelativePriceRule::new(f64::NAN, 0.0001, 1440.0, 10).is_err());
+        assert!(AdaptiveRelativePriceRule::new(0.01, f64::NAN, 1440.0, 10).is_err());
+
+        // Targeting one minute candles
+        let rule = AdaptiveRelativePriceRule::new(0.01, 0.0001, 1440.0, 3).unwrap();
+        let mut aggregator = GenericAggregator::<OhlcCandle, _, Trade>::new(rule, false);
+
+        // The price rises by 0.1% every second, so a minute has a variance of 60 * ln(1.001)^2
+        let r = 1.001_f64.ln();
+        let mut num_candles = 0;
+        for i in 0..3600 {
+            let trade = Trade {
+                timestamp: i * 1000,
+                price: 100.0 * (r * i as f64).exp(),
+                size: 1.0,
+            };
+            if aggregator.update(&trade).is_some() {
+                num_candles += 1;
+            }
+        }
+        let threshold = aggregator.aggregation_rule().current_threshold();
+        assert!(
+            (threshold / (60.0_f64.sqrt() * r) - 1.0).abs() < 0.01,
+            "{threshold}"
+        );
+        // A trending price moves further than the volatility suggests, so there are more candles
+        assert!(num_candles > 60, "{num_candles}");
+    }
+
+    #[test]
+    fn adaptive_relative_price_rule_flat_market() {
+        let mut rule = AdaptiveRelativePriceRule::new(0.01, 0.002, 1440.0, 3).unwrap();
+        // Candles finished by other rules in a flat market have no variance
+        for i in 0..100 {
+            let trade = Trade {
+                timestamp: i * 60_000,
+                price: 100.0,
+                size: 1.0,
+            };
+            assert!(!rule.should_trigger(&trade, &OhlcCandle::default()));
+            AggregationRule::<OhlcCandle, Trade>::reset(&mut rule, &trade);
+        }
+        assert_eq!(rule.current_threshold(), 0.002);
+    }
+}
@@ -0,0 +1,92 @@
+use crate::{ewma::Ewma, Error, Result, TakerTrade};
+
+const SECONDS_PER_DAY: f64 = 86_400.0;
+
+/// The shared state of the adaptive rules, which is the online form of `candle_volume_from_time_period`.
+/// After every candle, its activity (e.g. its volume) and its duration,
+/// which is the time since the previous candle was finished, are added to separate EWMAs.
+/// The ratio of the two, the expected activity per second, multiplied by the targeted candle duration,
+/// is the activity the next candle is expected to need for the configured number of candles per day.
+/// Using the ratio of the averages, instead of averaging the rate of each candle,
+/// keeps short candles from dominating the estimate.
+/// Candles finished within the same timestamp have no duration,
+/// so their activity is added to the next candle which has one.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub(crate) struct AdaptiveThreshold {
+    // The targeted duration of a candle in seconds
+    candle_seconds: f64,
+
+    // The expected activity of a candle
+    expected_activity: Ewma,
+
+    // The expected duration of a candle in seconds
+    expected_seconds: Ewma,
+
+    // The activity of the candles finished at the timestamp of `candle_start`
+    pending_activity: f64,
+
+    // The timestamp of the trade that finished the previous candle,
+    // or of the first trade before the first candle was finished
+    candle_start: Option<i64>,
+}
+
+impl AdaptiveThreshold {
+    /// # Arguments:
+    /// `initial_activity`: The activity per candle to expect until the first candle has been finished
+    /// `candles_per_day`: The targeted number of candles per day
+    /// `ewma_span`: The number of past candles the activity and duration are smoothed over
+    pub(crate) fn new(
+        initial_activity: f64,
+        candles_per_day: f64,
+        ewma_span: usize,
+    ) -> Result<Self> {
+        if !initial_activity.is_finite()
+            || initial_activity <= 0.0
+            || !candles_per_day.is_finite()
+            || candles_per_day <= 0.0
+            || ewma_span == 0
+        {
+            return Err(Error::InvalidParam);
+        }
+        let candle_seconds = SECONDS_PER_DAY / candles_per_day;
+
+        Ok(Self {
+            candle_seconds,
+            expected_activity: Ewma::new(ewma_span, initial_activity),
+            expected_seconds: Ewma::new(ewma_span, candle_seconds),
+            pending_activity: 0.0,
+            candle_start: None,
+        })
+    }
+
+    /// The activity the current candle is expected to need to meet the targeted number of candles per day
+    pub(crate) fn expected_activity(&self) -> f64 {
+        self.expected_activity.value() / self.expected_seconds.value() * self.candle_seconds
+    }
+
+    /// Starts the timing of the first candle, if it has not been started yet
+    pub(crate) fn start<T: TakerTrade>(&mut self, trade: &T) {
+        self.candle_start.get_or_insert(trade.timestamp());
+    }
+
+    /// Updates the expected activity and duration with a finished candle
+    ///
+    /// # Arguments:
+    /// `trade`: The trade that finished the candle
+    /// `activity`: The activity of the finished candle
+    pub(crate) fn finish<T: TakerTrade>(&mut self, trade: &T, activity: f64) {
+        let ts = trade.timestamp();
+        let start = *self.candle_start.get_or_insert(ts);
+        self.pending_activity += activity;
+        if ts <= start {
+            return;
+        }
+
+        let seconds = (ts - start) as f64 / trade.timestamp_resolution().units_per_second() as f64;
+        self.expected_activity.add(self.pending_activity);
+        self.expected_seconds.add(seconds);
+        self.pending_activity = 0.0;
+        self.candle_start = Some(ts);
+    }
+}
@@ -0,0 +1,116 @@
+use super::adaptive_threshold::AdaptiveThreshold;
+use crate::{AggregationRule, ModularCandle, Result, TakerTrade, TriggerReason};
+
+/// Creates candles every n ticks, like the `TickRule`,
+/// but with a number of ticks that adapts to the trade frequency to target a number of candles per day.
+/// After every candle, the number of ticks is recomputed from the EWMAs of the number of ticks of each past candle
+/// and of their durations.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AdaptiveTickRule {
+    init: bool,
+    tick_counter: usize,
+    n_ticks: usize,
+    adaptive: AdaptiveThreshold,
+}
+
+impl AdaptiveTickRule {
+    /// Create a new instance of the `AdaptiveTickRule`
+    ///
+    /// # Arguments:
+    /// `initial_n_ticks`: The number of ticks of the first candle
+    /// `candles_per_day`: The number of candles per day the number of ticks targets
+    /// `ewma_span`: The number of past candles the number of ticks and durations are smoothed over
+    ///
+    pub fn new(initial_n_ticks: usize, candles_per_day: f64, ewma_span: usize) -> Result<Self> {
+        Ok(Self {
+            init: true,
+            tick_counter: 0,
+            n_ticks: initial_n_ticks,
+            adaptive: AdaptiveThreshold::new(initial_n_ticks as f64, candles_per_day, ewma_span)?,
+        })
+    }
+
+    /// The number of ticks of the current candle
+    pub fn current_threshold(&self) -> usize {
+        self.n_ticks
+    }
+
+    fn finish<T: TakerTrade>(&mut self, trade: &T) {
+        self.adaptive.finish(trade, self.tick_counter as f64);
+        self.n_ticks = (self.adaptive.expected_activity().round() as usize).max(1);
+        self.init = true;
+    }
+}
+
+impl<C, T> AggregationRule<C, T> for AdaptiveTickRule
+where
+    C: ModularCandle<T>,
+    T: TakerTrade,
+{
+    fn should_trigger(&mut self, trade: &T, _candle: &C) -> bool {
+        self.adaptive.start(trade);
+        if self.init {
+            self.tick_counter = 0;
+            self.init = false;
+        }
+
+        self.tick_counter += 1;
+
+        if self.tick_counter >= self.n_ticks {
+            self.finish(trade);
+            return true;
+        }
+        false
+    }
+
+    fn reset(&mut self, trade: &T) {
+        if !self.init {
+            self.finish(trade);
+        }
+    }
+}
+
+impl TriggerReason for AdaptiveTickRule {
+    /// The number of ticks counted
+    type Reason = usize;
+
+    fn trigger_reason(&self) -> usize {
+        self.tick_counter
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{plot::OhlcCandle, Aggregator, GenericAggregator, Trade};
+
+    #[test]
+    fn adaptive_tick_rule() {
+        assert!(AdaptiveTickRule::new(0, 1440.0, 10).is_err());
+        assert!(AdaptiveTickRule::new(10, f64::NAN, 10).is_err());
+
+        // Targeting one minute candles
+        let rule = AdaptiveTickRule::new(10, 1440.0, 3).unwrap();
+        let mut aggregator = GenericAggregator::<OhlcCandle, _, Trade>::new(rule, false);
+
+        // One hour with a trade every second, then one hour with a trade every half second
+        let mut timestamp = 0;
+        for (interval, expected) in [(1000, 60), (500, 120)] {
+            let mut num_candles = 0;
+            for _ in 0..3_600_000 / interval {
+                timestamp += interval;
+                let trade = Trade {
+                    timestamp,
+                    price: 100.0,
+                    size: 1.0,
+                };
+                if aggregator.update(&trade).is_some() {
+                    num_candles += 1;
+                }
+            }
+            assert_eq!(aggregator.aggregation_rule().current_threshold(), expected);
+            assert!(num_candles >= 60, "{num_candles}");
+        }
+    }
+}
@@ -0,0 +1,183 @@
+use super::adaptive_threshold::AdaptiveThreshold;
+use crate::{AggregationRule, By, ModularCandle, Result, TakerTrade, TriggerReason};
+
+/// Creates candles every n units of volume traded, like the `VolumeRule`,
+/// but with a threshold that adapts to the traded volume to target a number of candles per day.
+/// After every candle, the threshold is recomputed from the EWMAs of the volume traded during each past candle
+/// and of their durations.
+/// If the last trade needed to complete a bucket is for a size greater than required,
+/// the excess size is given to the next bucket.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AdaptiveVolumeRule {
+    /// See docs on By enum for details
+    by: By,
+
+    /// cumulative volume
+    cum_vol: f64,
+
+    /// The volume traded since the previous candle was finished, without the excess carried over to it
+    traded_vol: f64,
+
+    /// The cumulative volume of the most recently finished candle, including the excess
+    last_vol: f64,
+
+    /// The theshold volume the current candle needs to have before finishing it
+    threshold_vol: f64,
+
+    adaptive: AdaptiveThreshold,
+}
+
+impl AdaptiveVolumeRule {
+    /// Create a new instance of the `AdaptiveVolumeRule`
+    ///
+    /// # Arguments:
+    /// `initial_threshold_vol`: The volume threshold of the first candle
+    /// `by`: The unit in which the volume is measured, see `By`
+    /// `candles_per_day`: The number of candles per day the threshold targets
+    /// `ewma_span`: The number of past candles the volume and durations are smoothed over
+    ///
+    pub fn new(
+        initial_threshold_vol: f64,
+        by: By,
+        candles_per_day: f64,
+        ewma_span: usize,
+    ) -> Result<Self> {
+        Ok(Self {
+            by,
+            cum_vol: 0.0,
+            traded_vol: 0.0,
+            last_vol: 0.0,
+            threshold_vol: initial_threshold_vol,
+            adaptive: AdaptiveThreshold::new(initial_threshold_vol, candles_per_day, ewma_span)?,
+        })
+    }
+
+    /// The volume threshold of the current candle
+    pub fn current_threshold(&self) -> f64 {
+        self.threshold_vol
+    }
+}
+
+impl<C, T> AggregationRule<C, T> for AdaptiveVolumeRule
+where
+    C: ModularCandle<T>,
+    T: TakerTrade,
+{
+    fn should_trigger(&mut self, trade: &T, _candle: &C) -> bool {
+        self.adaptive.start(trade);
+        let volume = self.by.volume(trade, trade.size());
+        self.cum_vol += volume;
+        self.traded_vol += volume;
+        if self.cum_vol < self.threshold_vol {
+            return false;
+        }
+
+        self.last_vol = self.cum_vol;
+        // The excess volume is given to the next bucket
+        self.cum_vol -= self.threshold_vol;
+        // Only the traded volume measures the activity, as the excess of a large trade
+        // may finish several candles without any further trading
+        self.adaptive.finish(trade, self.traded_vol);
+        self.traded_vol = 0.0;
+        self.threshold_vol = self.adaptive.expected_activity();
+
+        true
+    }
+
+    fn reset(&mut self, trade: &T) {
+        // The candle was finished by another rule, so there is no excess volume to carry over,
+        // but the trade is part of the new candle, just like the excess of a trade finishing a bucket.
+        self.adaptive.finish(trade, self.traded_vol);
+        self.threshold_vol = self.adaptive.expected_activity();
+        self.cum_vol = self.by.volume(trade, trade.size());
+        self.traded_vol = 0.0;
+    }
+}
+
+impl TriggerReason for AdaptiveVolumeRule {
+    /// The cumulative volume, including the excess that is carried over to the next bucket
+    type Reason = f64;
+
+    fn trigger_reason(&self) -> f64 {
+        self.last_vol
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{plot::OhlcCandle, Aggregator, GenericAggregator, Trade};
+
+    #[test]
+    fn adaptive_volume_rule() {
+        assert!(AdaptiveVolumeRule::new(0.0, By::Quote, 1440.0, 10).is_err());
+        assert!(AdaptiveVolumeRule::new(10.0, By::Quote, 0.0, 10).is_err());
+        assert!(AdaptiveVolumeRule::new(10.0, By::Quote, 1440.0, 0).is_err());
+        assert!(AdaptiveVolumeRule::new(f64::NAN, By::Quote, 1440.0, 10).is_err());
+        assert!(AdaptiveVolumeRule::new(10.0, By::Quote, f64::INFINITY, 10).is_err());
+
+        // Targeting one minute candles
+        let rule = AdaptiveVolumeRule::new(10.0, By::Quote, 1440.0, 3).unwrap();
+        let mut aggregator = GenericAggregator::<OhlcCandle, _, Trade>::new(rule, false);
+
+        // One hour of trades every second, first with a size of 1, then with a size of 2
+        let mut num_candles = vec![0; 2];
+        for (hour, size) in [1.0, 2.0].iter().enumerate() {
+            for i in 0..3600 {
+                let trade = Trade {
+                    timestamp: (hour as i64 * 3600 + i) * 1000,
+                    price: 100.0,
+                    size: *size,
+                };
+                if aggregator.update(&trade).is_some() {
+                    num_candles[hour] += 1;
+                }
+            }
+            // The threshold converged to one minute of volume
+            let threshold = aggregator.aggregation_rule().current_threshold();
+            assert!(
+                (threshold / (60.0 * size) - 1.0).abs() < 0.02,
+                "{threshold}"
+            );
+        }
+        // The first hour starts out with too many candles, which the second hour no longer does
+        assert!(num_candles[0] > 60);
+        assert!((59..=61).contains(&num_candles[1]), "{num_candles:?}");
+    }
+
+    #[test]
+    fn adaptive_volume_rule_burst() {
+        // Targeting one minute candles
+        let rule = AdaptiveVolumeRule::new(60.0, By::Quote, 1440.0, 3).unwrap();
+        let mut aggregator = GenericAggregator::<OhlcCandle, _, Trade>::new(rule, false);
+        let mut update = |timestamp: i64, size: f64| {
+            aggregator.update(&Trade {
+                timestamp,
+                price: 100.0,
+                size,
+            });
+            aggregator.aggregation_rule().current_threshold()
+        };
+
+        // A steady volume of one per second
+        for i in 0..3600 {
+            update(i * 1000, 1.0);
+        }
+        // A sweep of size 600 and three more fills within the same millisecond,
+        // followed by another hour of steady trading
+        let mut max_threshold = 0.0_f64;
+        for size in [600.0, 1.0, 1.0, 1.0] {
+            max_threshold = max_threshold.max(update(3_600_000, size));
+        }
+        let mut threshold = 0.0;
+        for i in 1..3600 {
+            threshold = update(3_600_000 + i * 1000, 1.0);
+            max_threshold = max_threshold.max(threshold);
+        }
+
+        // The burst raises the threshold for a few candles, which then recovers
+        assert!(max_threshold < 600.0, "{max_threshold}");
+        assert!((threshold / 60.0 - 1.0).abs() < 0.02, "{threshold}");
+    }
+}
@@ -14,4 +14,44 @@ pub trait AggregationRule<C, T: TakerTrade> {
     /// if true, the aggregation period is finished and a Candle can be emitted
     /// else the aggregation needs to continue
     fn should_trigger(&mut self, trade: &T, candle: &C) -> bool;
+
+    /// Called when the current candle was finished by something other than this rule,
+    /// e.g. by a sibling rule inside of an `AnyRule` or `AllRule`.
+    /// The rule should start a new aggregation period, as if it had triggered on the given trade itself.
+    /// The given trade is part of the new candle and `should_trigger` is not called with it afterwards,
+    /// so a rule accumulating the trades of a candle has to count it towards the new period,
+    /// e.g. the `VolumeRule` starts the next bucket with the volume of the trade.
+    /// The default implementation does nothing.
+    ///
+    /// # Arguments:
+    /// trade: The taker trade on which the candle was finished
+    fn reset(&mut self, _trade: &T) {}
+
+    /// Called without a new trade, e.g. periodically from a wall clock timer in live trading,
+    /// allowing time based rules to finish a candle once its period has passed.
+    /// The default implementation never triggers.
+    ///
+    /// # Arguments:
+    /// now: The current time, in the unit of the trade timestamps
+    ///
+    /// # Returns:
+    /// if true, the aggregation period is finished and a Candle can be emitted
+    fn should_trigger_on_time(&mut self, _now: i64) -> bool {
+        false
+    }
+
+    /// Decides whether a trade takes part in the aggregation at all.
+    /// Trades that are not accepted are ignored by the aggregator,
+    /// so they are neither added to a candle nor passed to `should_trigger`,
+    /// e.g. trades outside of the configured sessions of a `SessionRule`.
+    /// The default implementation accepts every trade.
+    ///
+    /// # Arguments:
+    /// trade: The most recent taker trade (tick) information
+    ///
+    /// # Returns:
+    /// if false, the trade is dropped
+    fn accepts(&mut self, _trade: &T) -> bool {
+        true
+    }
 }
@@ -1,4 +1,7 @@
-use crate::{AggregationRule, MillisecondPeriod, ModularCandle, TakerTrade, TimestampResolution};
+use crate::{
+    AggregationRule, GapFill, MillisecondPeriod, ModularCandle, Result, TakerTrade,
+    TimestampResolution, TriggerReason,
+};
 
 /// The classic time based aggregation rule,
 /// creating a new candle every n seconds.  The time trigger is aligned such that
@@ -6,6 +9,7 @@ use crate::{AggregationRule, MillisecondPeriod, ModularCandle, TakerTrade, Times
 /// tick comes in a 1:32:00 on a 5 minute candle, that first candle will only contain
 /// 3 minutes of trades, representing a 1:30 start.
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct AlignedTimeRule {
     // The timestamp this rule uses as a reference
     reference_timestamp: i64,
@@ -14,6 +18,10 @@ pub struct AlignedTimeRule {
     // constants can be used nicely here from constants.rs
     // e.g.: M1 -> 1 minute candles
     period_in_units_from_trade: i64,
+
+    // The reference timestamp of the candle finished by `should_trigger_on_time`, until the next trade,
+    // so `GapFill::undo_on_time` can fill the periods after that candle.
+    on_time_reference: Option<i64>,
 }
 
 impl AlignedTimeRule {
@@ -24,21 +32,28 @@ impl AlignedTimeRule {
     /// period_s: How many seconds a candle will contain
     /// ts_res: The resolution each Trade timestamp will have
     ///
+    /// # Panics:
+    /// If the period is not a whole number of seconds for `TimestampResolution::Second`,
+    /// use `try_new` to handle such a period instead.
     pub fn new(
         period_ms: MillisecondPeriod,
         trade_timestamp_resolution: TimestampResolution,
     ) -> Self {
-        use TimestampResolution::*;
-        let ts_multiplier = match trade_timestamp_resolution {
-            Millisecond => 1,
-            Microsecond => 1_000,
-            Nanosecond => 1_000_000,
-        };
-
-        Self {
+        Self::try_new(period_ms, trade_timestamp_resolution)
+            .expect("The period must be a whole number of seconds for second timestamps")
+    }
+
+    /// Create a new instance like `new`,
+    /// but return an error if the period is not a whole number of seconds for `TimestampResolution::Second`.
+    pub fn try_new(
+        period_ms: MillisecondPeriod,
+        trade_timestamp_resolution: TimestampResolution,
+    ) -> Result<Self> {
+        Ok(Self {
             reference_timestamp: 0,
-            period_in_units_from_trade: period_ms.get() as i64 * ts_multiplier,
-        }
+            period_in_units_from_trade: trade_timestamp_resolution.period_in_units(period_ms)?,
+            on_time_reference: None,
+        })
     }
 
     /// Calculates the "aligned" timestamp, which the rule will use when receiving
@@ -56,6 +71,7 @@ where
     T: TakerTrade,
 {
     fn should_trigger(&mut self, trade: &T, _candle: &C) -> bool {
+        self.on_time_reference = None;
         if self.reference_timestamp == 0 {
             self.reference_timestamp = self.aligned_timestamp(trade.timestamp());
             return false;
@@ -68,11 +84,62 @@ where
             // If the period is too small, then the trade timestamps will out-pace and always trigger.
             // Alternatively doing `self.reference_timestamp = self.aligned_timestamp(trade.timestamp())` will cause drift
             // and not produce enough samples.
-            self.reference_timestamp = self.reference_timestamp + self.period_in_units_from_trade
+            self.reference_timestamp += self.period_in_units_from_trade
         }
 
         should_trigger
     }
+
+    fn reset(&mut self, trade: &T) {
+        // Start over with the period containing the trade, e.g. at the open of a trading session.
+        self.reference_timestamp = self.aligned_timestamp(trade.timestamp());
+    }
+
+    fn should_trigger_on_time(&mut self, now: i64) -> bool {
+        if self.reference_timestamp == 0 {
+            // No trade has been observed yet
+            return false;
+        }
+        let reference_timestamp = self.reference_timestamp;
+        let mut should_trigger = false;
+        // Advance to the period containing `now`, as there is no point in triggering once for each passed period.
+        while now - self.reference_timestamp >= self.period_in_units_from_trade {
+            self.reference_timestamp += self.period_in_units_from_trade;
+            should_trigger = true;
+        }
+        if should_trigger {
+            self.on_time_reference.get_or_insert(reference_timestamp);
+        }
+
+        should_trigger
+    }
+}
+
+impl GapFill for AlignedTimeRule {
+    fn next_empty_period(&mut self, timestamp: i64) -> Option<(i64, i64)> {
+        if timestamp - self.reference_timestamp < self.period_in_units_from_trade {
+            return None;
+        }
+        let open = self.reference_timestamp;
+        self.reference_timestamp += self.period_in_units_from_trade;
+
+        Some((open, self.reference_timestamp - 1))
+    }
+
+    fn undo_on_time(&mut self) {
+        if let Some(reference_timestamp) = self.on_time_reference.take() {
+            self.reference_timestamp = reference_timestamp;
+        }
+    }
+}
+
+impl TriggerReason for AlignedTimeRule {
+    /// The start of the new period, in the unit of the incoming trades
+    type Reason = i64;
+
+    fn trigger_reason(&self) -> i64 {
+        self.reference_timestamp
+    }
 }
 
 #[cfg(test)]
@@ -82,12 +149,10 @@ mod tests {
     use super::*;
     use crate::{
         aggregate_all_trades,
-        candle_components::{
-            CandleComponent, CandleComponentUpdate, Close, NumTrades, Open, Volume,
-        },
+        candle_components::{Close, NumTrades, Open, Volume},
         load_trades_from_csv,
         plot::OhlcCandle,
-        GenericAggregator, ModularCandle, TimestampResolution, Trade, M1, M15,
+        Aggregator, GenericAggregator, TimestampResolution, Trade, M1, M15,
     };
 
     #[derive(Default, Debug, Clone, Candle)]
@@ -212,4 +277,81 @@ mod tests {
         assert_eq!(candles[1].open(), 100.5);
         assert_eq!(candles[1].close(), 100.5);
     }
+
+    #[test]
+    fn aligned_time_rule_filling_gaps() {
+        let trades: [Trade; 4] = [
+            Trade {
+                timestamp: 1712656800000,
+                price: 100.0,
+                size: 10.0,
+            },
+            Trade {
+                timestamp: 1712656815000,
+                price: 101.0,
+                size: -10.0,
+            },
+            Trade {
+                timestamp: 1712656861000,
+                price: 100.5,
+                size: -10.0,
+            },
+            Trade {
+                timestamp: 1712657930000,
+                price: 102.0,
+                size: -10.0,
+            },
+        ];
+
+        let mut aggregator = GenericAggregator::<OhlcCandle, AlignedTimeRule, Trade>::new(
+            AlignedTimeRule::new(M1, TimestampResolution::Millisecond),
+            false,
+        );
+        let candles: Vec<OhlcCandle> = trades
+            .iter()
+            .flat_map(|t| aggregator.update_filling_gaps(t))
+            .collect();
+        // 2 regular candles followed by the 16 minutes without trades.
+        assert_eq!(candles.len(), 18);
+        assert_eq!(candles[1].open(), 100.5);
+        assert_eq!(candles[1].close(), 100.5);
+        for c in &candles[2..] {
+            assert_eq!(c.open(), 100.5);
+            assert_eq!(c.high(), 100.5);
+            assert_eq!(c.low(), 100.5);
+            assert_eq!(c.close(), 100.5);
+        }
+        assert_eq!(aggregator.unfinished_candle().open(), 102.0);
+    }
+
+    #[test]
+    fn aligned_time_rule_second_resolution() {
+        let trades = load_trades_from_csv("data/Bitstamp_BTCEUR_1M.csv").unwrap();
+        let trades_s: Vec<Trade> = trades
+            .iter()
+            .map(|t| Trade {
+                timestamp: t.timestamp / 1_000_000,
+                ..*t
+            })
+            .collect();
+        let period = crate::MillisecondPeriod::from_non_zero_secs(10);
+
+        let mut aggregator = GenericAggregator::<MyCandle, AlignedTimeRule, Trade>::new(
+            AlignedTimeRule::new(period, TimestampResolution::Microsecond),
+            false,
+        );
+        let expected = aggregate_all_trades(&trades, &mut aggregator);
+        let mut aggregator = GenericAggregator::<MyCandle, AlignedTimeRule, Trade>::new(
+            AlignedTimeRule::new(period, TimestampResolution::Second),
+            false,
+        );
+        let candles = aggregate_all_trades(&trades_s, &mut aggregator);
+
+        assert_eq!(candles.len(), 6);
+        assert_eq!(candles.len(), expected.len());
+        for (c, e) in candles.iter().zip(expected.iter()) {
+            assert_eq!(c.open(), e.open());
+            assert_eq!(c.num_trades(), e.num_trades());
+        }
+    }
 }
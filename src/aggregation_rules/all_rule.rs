@@ -0,0 +1,259 @@
+use crate::{
+    AggregationRule, CombinedReason, ModularCandle, TakerTrade, TriggerReason, TriggeredBy,
+};
+
+/// Combines two rules, creating a new candle only once both of them have triggered,
+/// e.g.: only after at least 1 minute and 50 BTC of volume.
+/// The rules don't have to trigger on the same trade, as a triggered rule is remembered until the candle is finished.
+/// Once the candle is finished, the rules that did not trigger on the finishing trade are reset,
+/// so they start a new aggregation period together with the new candle.
+/// Use the `all_rule!` macro to combine more than two rules.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AllRule<A, B> {
+    first: A,
+    second: B,
+    // Whether the rules have triggered since the start of the current candle
+    first_triggered: bool,
+    second_triggered: bool,
+    triggered_by: Option<TriggeredBy>,
+    // Set when a candle was finished on time, so the rules that did not trigger
+    // are reset with the next trade.
+    reset_first: bool,
+    reset_second: bool,
+}
+
+impl<A, B> AllRule<A, B> {
+    /// Create a new instance of the `AllRule`
+    ///
+    /// # Arguments:
+    /// `first`: The first rule
+    /// `second`: The second rule
+    ///
+    pub fn new(first: A, second: B) -> Self {
+        Self {
+            first,
+            second,
+            first_triggered: false,
+            second_triggered: false,
+            triggered_by: None,
+            reset_first: false,
+            reset_second: false,
+        }
+    }
+
+    /// Which rule triggered on the most recent trade and thereby finished the candle, if any
+    pub fn triggered_by(&self) -> Option<TriggeredBy> {
+        self.triggered_by
+    }
+
+    /// Get a reference to the first rule
+    pub fn first(&self) -> &A {
+        &self.first
+    }
+
+    /// Get a reference to the second rule
+    pub fn second(&self) -> &B {
+        &self.second
+    }
+}
+
+impl<A, B, C, T> AggregationRule<C, T> for AllRule<A, B>
+where
+    A: AggregationRule<C, T>,
+    B: AggregationRule<C, T>,
+    C: ModularCandle<T>,
+    T: TakerTrade,
+{
+    fn should_trigger(&mut self, trade: &T, candle: &C) -> bool {
+        // Both rules must see every trade to keep their state consistent, so don't short circuit.
+        let mut first = self.first.should_trigger(trade, candle);
+        let mut second = self.second.should_trigger(trade, candle);
+
+        // After a candle was finished on time, the rules that did not trigger
+        // start their new period with this trade, once they have observed it.
+        if std::mem::take(&mut self.reset_first) {
+            self.first.reset(trade);
+            first = false;
+        }
+        if std::mem::take(&mut self.reset_second) {
+            self.second.reset(trade);
+            second = false;
+        }
+        self.first_triggered |= first;
+        self.second_triggered |= second;
+
+        if !(self.first_triggered && self.second_triggered) {
+            self.triggered_by = None;
+            return false;
+        }
+
+        // A rule that triggered earlier during the candle has already started its next period,
+        // so realign it with the new candle.
+        if !first {
+            self.first.reset(trade);
+        }
+        if !second {
+            self.second.reset(trade);
+        }
+        self.first_triggered = false;
+        self.second_triggered = false;
+        self.triggered_by = TriggeredBy::from_flags(first, second);
+
+        true
+    }
+
+    fn reset(&mut self, trade: &T) {
+        self.first.reset(trade);
+        self.second.reset(trade);
+        self.first_triggered = false;
+        self.second_triggered = false;
+        self.triggered_by = None;
+        self.reset_first = false;
+        self.reset_second = false;
+    }
+
+    fn accepts(&mut self, trade: &T) -> bool {
+        // Rules may update their state when deciding, so both must see every trade. Don't short circuit.
+        let first = self.first.accepts(trade);
+        let second = self.second.accepts(trade);
+        first && second
+    }
+
+    fn should_trigger_on_time(&mut self, now: i64) -> bool {
+        let first = self.first.should_trigger_on_time(now);
+        let second = self.second.should_trigger_on_time(now);
+        self.first_triggered |= first;
+        self.second_triggered |= second;
+
+        if !(self.first_triggered && self.second_triggered) {
+            self.triggered_by = None;
+            return false;
+        }
+
+        self.reset_first |= !first;
+        self.reset_second |= !second;
+        self.first_triggered = false;
+        self.second_triggered = false;
+        self.triggered_by = TriggeredBy::from_flags(first, second);
+
+        true
+    }
+}
+
+impl<A, B> TriggerReason for AllRule<A, B>
+where
+    A: TriggerReason,
+    B: TriggerReason,
+{
+    type Reason = CombinedReason<A::Reason, B::Reason>;
+
+    fn trigger_reason(&self) -> Self::Reason {
+        CombinedReason {
+            triggered_by: self.triggered_by,
+            first: self.first.trigger_reason(),
+            second: self.second.trigger_reason(),
+        }
+    }
+}
+
+/// Combine any number of `AggregationRule`s into nested `AllRule`s,
+/// creating a new candle only once all of them have triggered.
+///
+/// e.g.: `all_rule!(TimeRule::new(M1, TimestampResolution::Millisecond), VolumeRule::new(50.0, By::Quote)?)`
+#[macro_export]
+macro_rules! all_rule {
+    ($rule:expr $(,)?) => {
+        $rule
+    };
+    ($first:expr, $($rest:expr),+ $(,)?) => {
+        $crate::AllRule::new($first, $crate::all_rule!($($rest),+))
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        aggregate_all_trades, candle_components::tests::TRADES, plot::OhlcCandle, By,
+        GenericAggregator, MillisecondPeriod, TickRule, TimeRule, TimestampResolution, Trade,
+        VolumeRule,
+    };
+
+    #[test]
+    fn all_rule() {
+        // At least 3 ticks and 30 units of volume
+        let mut rule = AllRule::new(TickRule::new(3), VolumeRule::new(30.0, By::Quote).unwrap());
+        let candle = OhlcCandle::default();
+
+        let mut reasons = vec![];
+        for t in TRADES.iter() {
+            rule.should_trigger(t, &candle);
+            reasons.push(rule.triggered_by());
+        }
+        use TriggeredBy::*;
+        assert_eq!(
+            reasons,
+            vec![
+                None,
+                None,
+                Some(Both),
+                None,
+                None,
+                Some(First),
+                None,
+                None,
+                Some(Both),
+                None
+            ]
+        );
+    }
+
+    #[test]
+    fn all_rule_accepts_evaluates_both() {
+        // Rejects every other trade and counts how many trades it has seen
+        #[derive(Default)]
+        struct EveryOther(usize);
+
+        impl AggregationRule<OhlcCandle, Trade> for EveryOther {
+            fn should_trigger(&mut self, _trade: &Trade, _candle: &OhlcCandle) -> bool {
+                false
+            }
+
+            fn accepts(&mut self, _trade: &Trade) -> bool {
+                self.0 += 1;
+                self.0 % 2 == 1
+            }
+        }
+
+        let mut rule = AllRule::new(EveryOther::default(), EveryOther::default());
+        let accepted: Vec<bool> = TRADES.iter().take(4).map(|t| rule.accepts(t)).collect();
+        assert_eq!(accepted, vec![true, false, true, false]);
+        assert_eq!(rule.first().0, 4);
+        assert_eq!(rule.second().0, 4);
+
+        let mut rule = AllRule::new(EveryOther(1), EveryOther::default());
+        let accepted: Vec<bool> = TRADES.iter().take(4).map(|t| rule.accepts(t)).collect();
+        assert_eq!(accepted, vec![false, false, false, false]);
+        assert_eq!(rule.second().0, 4);
+    }
+
+    #[test]
+    fn all_rule_time_and_volume() {
+        // At least 30 seconds and 40 units of volume
+        let rule = all_rule!(
+            TimeRule::new(
+                MillisecondPeriod::from_non_zero_secs(30),
+                TimestampResolution::Millisecond
+            ),
+            VolumeRule::new(40.0, By::Quote).unwrap(),
+        );
+        let mut aggregator = GenericAggregator::<OhlcCandle, _, Trade>::new(rule, false);
+        let candles = aggregate_all_trades(&TRADES, &mut aggregator);
+        assert_eq!(candles.len(), 2);
+        assert_eq!(candles[0].open(), 100.0);
+        assert_eq!(candles[0].close(), 102.0);
+        assert_eq!(candles[1].open(), 103.0);
+        assert_eq!(candles[1].close(), 102.0);
+    }
+}
@@ -0,0 +1,278 @@
+use crate::{
+    AggregationRule, CombinedReason, ModularCandle, TakerTrade, TriggerReason, TriggeredBy,
+};
+
+/// Combines two rules, creating a new candle as soon as either of them triggers,
+/// e.g.: every 1000 ticks or every 5 minutes, whichever comes first.
+/// Both rules observe every trade. The rule that did not trigger is reset,
+/// so it starts a new aggregation period together with the new candle,
+/// while the triggering rule keeps its own state, e.g. the excess volume of a `VolumeRule`.
+/// Use the `any_rule!` macro to combine more than two rules.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AnyRule<A, B> {
+    first: A,
+    second: B,
+    triggered_by: Option<TriggeredBy>,
+    // Set when a candle was finished on time, so the rule that did not trigger
+    // is reset with the next trade.
+    reset_first: bool,
+    reset_second: bool,
+}
+
+impl<A, B> AnyRule<A, B> {
+    /// Create a new instance of the `AnyRule`
+    ///
+    /// # Arguments:
+    /// `first`: The first rule
+    /// `second`: The second rule
+    ///
+    pub fn new(first: A, second: B) -> Self {
+        Self {
+            first,
+            second,
+            triggered_by: None,
+            reset_first: false,
+            reset_second: false,
+        }
+    }
+
+    /// Which rule triggered on the most recent trade, if any
+    pub fn triggered_by(&self) -> Option<TriggeredBy> {
+        self.triggered_by
+    }
+
+    /// Get a reference to the first rule
+    pub fn first(&self) -> &A {
+        &self.first
+    }
+
+    /// Get a reference to the second rule
+    pub fn second(&self) -> &B {
+        &self.second
+    }
+}
+
+impl<A, B, C, T> AggregationRule<C, T> for AnyRule<A, B>
+where
+    A: AggregationRule<C, T>,
+    B: AggregationRule<C, T>,
+    C: ModularCandle<T>,
+    T: TakerTrade,
+{
+    fn should_trigger(&mut self, trade: &T, candle: &C) -> bool {
+        // Both rules must see every trade to keep their state consistent, so don't short circuit.
+        let mut first = self.first.should_trigger(trade, candle);
+        let mut second = self.second.should_trigger(trade, candle);
+
+        // After a candle was finished on time, the rules that did not trigger
+        // start their new period with this trade, once they have observed it.
+        if std::mem::take(&mut self.reset_first) {
+            self.first.reset(trade);
+            first = false;
+        }
+        if std::mem::take(&mut self.reset_second) {
+            self.second.reset(trade);
+            second = false;
+        }
+
+        self.triggered_by = TriggeredBy::from_flags(first, second);
+        match self.triggered_by {
+            Some(TriggeredBy::First) => self.second.reset(trade),
+            Some(TriggeredBy::Second) => self.first.reset(trade),
+            Some(TriggeredBy::Both) | None => {}
+        }
+
+        self.triggered_by.is_some()
+    }
+
+    fn reset(&mut self, trade: &T) {
+        self.first.reset(trade);
+        self.second.reset(trade);
+        self.triggered_by = None;
+        self.reset_first = false;
+        self.reset_second = false;
+    }
+
+    fn accepts(&mut self, trade: &T) -> bool {
+        // Rules may update their state when deciding, so both must see every trade. Don't short circuit.
+        let first = self.first.accepts(trade);
+        let second = self.second.accepts(trade);
+        first && second
+    }
+
+    fn should_trigger_on_time(&mut self, now: i64) -> bool {
+        let first = self.first.should_trigger_on_time(now);
+        let second = self.second.should_trigger_on_time(now);
+
+        self.triggered_by = TriggeredBy::from_flags(first, second);
+        if self.triggered_by.is_some() {
+            self.reset_first |= !first;
+            self.reset_second |= !second;
+        }
+
+        self.triggered_by.is_some()
+    }
+}
+
+impl<A, B> TriggerReason for AnyRule<A, B>
+where
+    A: TriggerReason,
+    B: TriggerReason,
+{
+    type Reason = CombinedReason<A::Reason, B::Reason>;
+
+    fn trigger_reason(&self) -> Self::Reason {
+        CombinedReason {
+            triggered_by: self.triggered_by,
+            first: self.first.trigger_reason(),
+            second: self.second.trigger_reason(),
+        }
+    }
+}
+
+/// Combine any number of `AggregationRule`s into nested `AnyRule`s,
+/// creating a new candle as soon as any of them triggers.
+///
+/// e.g.: `any_rule!(TickRule::new(1000), TimeRule::new(M5, TimestampResolution::Millisecond))`
+#[macro_export]
+macro_rules! any_rule {
+    ($rule:expr $(,)?) => {
+        $rule
+    };
+    ($first:expr, $($rest:expr),+ $(,)?) => {
+        $crate::AnyRule::new($first, $crate::any_rule!($($rest),+))
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        aggregate_all_trades, candle_components::tests::TRADES, plot::OhlcCandle, By,
+        GenericAggregator, MillisecondPeriod, TickRule, TimeRule, TimestampResolution, Trade,
+        VolumeRule,
+    };
+
+    #[test]
+    fn any_rule() {
+        // 2 ticks or 30 units of volume.
+        // Once the tick rule triggers, the volume rule starts its next bucket with the volume of the triggering trade.
+        let mut rule = AnyRule::new(TickRule::new(2), VolumeRule::new(30.0, By::Quote).unwrap());
+        let candle = OhlcCandle::default();
+
+        let mut reasons = vec![];
+        for t in TRADES.iter() {
+            rule.should_trigger(t, &candle);
+            reasons.push(rule.triggered_by());
+        }
+        use TriggeredBy::*;
+        assert_eq!(
+            reasons,
+            vec![
+                None,
+                Some(First),
+                Some(Second),
+                None,
+                Some(First),
+                Some(Second),
+                None,
+                Some(First),
+                Some(Second),
+                None
+            ]
+        );
+    }
+
+    #[test]
+    fn any_rule_keeps_volume_excess() {
+        // The volume rule carries the excess of 10 units over into the next bucket,
+        // which must survive the tick rule not triggering.
+        let mut rule = AnyRule::new(TickRule::new(10), VolumeRule::new(30.0, By::Quote).unwrap());
+        let candle = OhlcCandle::default();
+
+        let triggers: Vec<bool> = TRADES
+            .iter()
+            .map(|t| rule.should_trigger(t, &candle))
+            .collect();
+        assert_eq!(
+            triggers,
+            vec![false, false, true, false, true, false, true, false, true, false]
+        );
+    }
+
+    #[test]
+    fn any_rule_resets_sibling() {
+        // After the tick rule triggers, the time rule starts counting from the triggering trade,
+        // so it never gets to trigger as the trades are 10 seconds apart.
+        let mut aggregator = GenericAggregator::<OhlcCandle, _, Trade>::new(
+            any_rule!(
+                TickRule::new(3),
+                TimeRule::new(
+                    MillisecondPeriod::from_non_zero_secs(35),
+                    TimestampResolution::Millisecond
+                ),
+            ),
+            false,
+        );
+        let candles = aggregate_all_trades(&TRADES, &mut aggregator);
+        assert_eq!(candles.len(), 3);
+        assert_eq!(aggregator.aggregation_rule().triggered_by(), None);
+        assert_eq!(candles[0].open(), 100.0);
+        assert_eq!(candles[0].close(), 101.0);
+        assert_eq!(candles[1].open(), 100.0);
+        assert_eq!(candles[1].close(), 103.0);
+        assert_eq!(candles[2].open(), 104.0);
+        assert_eq!(candles[2].close(), 101.0);
+    }
+
+    #[test]
+    fn any_rule_macro() {
+        let mut rule = any_rule!(TickRule::new(100), TickRule::new(5), TickRule::new(2));
+        let candle = OhlcCandle::default();
+        for t in TRADES.iter().take(2) {
+            rule.should_trigger(t, &candle);
+        }
+        assert_eq!(rule.triggered_by(), Some(TriggeredBy::Second));
+        assert_eq!(rule.second().triggered_by(), Some(TriggeredBy::Second));
+    }
+
+    #[test]
+    fn any_rule_on_time() {
+        use crate::Aggregator;
+
+        let mut aggregator = GenericAggregator::<OhlcCandle, _, Trade>::new(
+            any_rule!(
+                TickRule::new(3),
+                TimeRule::new(
+                    MillisecondPeriod::from_non_zero_secs(25),
+                    TimestampResolution::Millisecond
+                ),
+            ),
+            false,
+        );
+        assert!(aggregator.update(&TRADES[0]).is_none());
+        assert!(aggregator.update(&TRADES[1]).is_none());
+
+        let candle = aggregator.on_time(TRADES[0].timestamp + 25_001).unwrap();
+        assert_eq!(candle.open(), 100.0);
+        assert_eq!(candle.close(), 101.0);
+        assert_eq!(
+            aggregator.aggregation_rule().triggered_by(),
+            Some(TriggeredBy::Second)
+        );
+
+        // The tick rule starts its new period with the next trade, which opens the new candle
+        assert!(aggregator.update(&TRADES[3]).is_none());
+        assert!(aggregator.update(&TRADES[4]).is_none());
+        assert!(aggregator.update(&TRADES[5]).is_none());
+        let candle = aggregator.update(&TRADES[6]).unwrap();
+        assert_eq!(candle.open(), 102.0);
+        assert_eq!(candle.close(), 104.0);
+        // The third tick is also more than 25 seconds after the period the candle was finished in
+        assert_eq!(
+            aggregator.aggregation_rule().triggered_by(),
+            Some(TriggeredBy::Both)
+        );
+    }
+}
@@ -0,0 +1,415 @@
+use chrono::{
+    DateTime, Datelike, Duration, LocalResult, Months, NaiveDate, NaiveDateTime, NaiveTime,
+    TimeZone, Weekday,
+};
+
+use crate::{AggregationRule, ModularCandle, TakerTrade, TimestampResolution, TriggerReason};
+
+/// The calendar unit of the candles created by the `CalendarRule`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum CalendarPeriod {
+    /// Every day
+    Day,
+
+    /// Every week, starting on the given weekday
+    Week(Weekday),
+
+    /// Every calendar month
+    Month,
+
+    /// Every calendar quarter, starting in January, April, July and October
+    Quarter,
+}
+
+/// Creates candles aligned to calendar days, weeks, months or quarters in a given time zone,
+/// e.g. daily candles starting at 17:00 New York time for FX,
+/// or weekly candles starting on Monday at midnight exchange time.
+/// A period starts at the session start time on its first day in the local time of the time zone,
+/// so the boundaries follow daylight saving time transitions.
+/// If the session start does not exist on a day, as it falls into a daylight saving time gap,
+/// the period starts at the end of the gap. If it is ambiguous, the earlier instant is used.
+///
+/// Any `chrono::TimeZone` can be used, e.g. `Utc`, a `FixedOffset`
+/// or an IANA time zone from the `chrono-tz` crate such as `chrono_tz::America::New_York`.
+/// Trades with a timestamp outside of the range of `chrono::DateTime` never finish a candle.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CalendarRule<Tz> {
+    period: CalendarPeriod,
+    timezone: Tz,
+    session_start: NaiveTime,
+    trade_timestamp_resolution: TimestampResolution,
+
+    // The start and end of the current period, in the unit of the incoming trades.
+    // The end is zero until the first trade has been observed.
+    period_start: i64,
+    period_end: i64,
+}
+
+impl<Tz: TimeZone> CalendarRule<Tz> {
+    /// Create a new instance of the calendar rule
+    ///
+    /// # Arguments:
+    /// `period`: The calendar unit of each candle
+    /// `timezone`: The time zone in which the calendar boundaries are determined
+    /// `session_start`: The local time of day at which a period starts, e.g. 17:00 for FX
+    /// `trade_timestamp_resolution`: The resolution each Trade timestamp will have
+    ///
+    pub fn new(
+        period: CalendarPeriod,
+        timezone: Tz,
+        session_start: NaiveTime,
+        trade_timestamp_resolution: TimestampResolution,
+    ) -> Self {
+        Self {
+            period,
+            timezone,
+            session_start,
+            trade_timestamp_resolution,
+            period_start: 0,
+            period_end: 0,
+        }
+    }
+
+    /// The start of the current period, in the local time of the time zone.
+    /// None if no trade has been observed yet.
+    pub fn period_start(&self) -> Option<DateTime<Tz>> {
+        if self.period_end == 0 {
+            return None;
+        }
+        let utc = self
+            .trade_timestamp_resolution
+            .datetime_from_timestamp(self.period_start)?;
+        Some(utc.with_timezone(&self.timezone))
+    }
+
+    /// The first day of the period containing the given local date
+    fn first_day(&self, date: NaiveDate) -> NaiveDate {
+        match self.period {
+            CalendarPeriod::Day => date,
+            CalendarPeriod::Week(start) => {
+                let days_since_start =
+                    (7 + date.weekday().num_days_from_monday() - start.num_days_from_monday()) % 7;
+                date - Duration::days(days_since_start as i64)
+            }
+            CalendarPeriod::Month => date.with_day(1).expect("Every month has a first day"),
+            CalendarPeriod::Quarter => {
+                let month = (date.month0() / 3) * 3 + 1;
+                NaiveDate::from_ymd_opt(date.year(), month, 1)
+                    .expect("The first day of a quarter exists")
+            }
+        }
+    }
+
+    /// The first day of the period following the period starting at `first_day`
+    fn next_first_day(&self, first_day: NaiveDate) -> NaiveDate {
+        match self.period {
+            CalendarPeriod::Day => first_day + Duration::days(1),
+            CalendarPeriod::Week(_) => first_day + Duration::days(7),
+            CalendarPeriod::Month => first_day + Months::new(1),
+            CalendarPeriod::Quarter => first_day + Months::new(3),
+        }
+    }
+
+    /// The instant at which the session starts on the given local date
+    fn session_start_on(&self, date: NaiveDate) -> DateTime<Tz> {
+        resolve_local(&self.timezone, date, self.session_start)
+    }
+
+    /// Sets the current period to the one containing the timestamp.
+    /// Returns false without changing the period, if the timestamp is outside of the range of `DateTime`.
+    fn align(&mut self, timestamp: i64) -> bool {
+        let res = self.trade_timestamp_resolution;
+        let Some(utc) = res.datetime_from_timestamp(timestamp) else {
+            return false;
+        };
+        let local = utc.with_timezone(&self.timezone);
+
+        // Before the session start, the local time still belongs to the session of the previous day.
+        let mut date = local.date_naive();
+        if self.session_start_on(date) > local {
+            date -= Duration::days(1);
+        }
+        let first_day = self.first_day(date);
+        let next_first_day = self.next_first_day(first_day);
+
+        self.period_start = res.timestamp_from_datetime(&self.session_start_on(first_day));
+        self.period_end = res.timestamp_from_datetime(&self.session_start_on(next_first_day));
+
+        true
+    }
+}
+
+/// The instant of a local date and time in the time zone.
+/// A local time inside of a daylight saving time gap resolves to the end of the gap,
+/// an ambiguous one to the earlier instant.
+pub(super) fn resolve_local<Tz: TimeZone>(
+    timezone: &Tz,
+    date: NaiveDate,
+    time: NaiveTime,
+) -> DateTime<Tz> {
+    let mut local = NaiveDateTime::new(date, time);
+    loop {
+        match timezone.from_local_datetime(&local) {
+            LocalResult::Single(dt) => return dt,
+            LocalResult::Ambiguous(earliest, _) => return earliest,
+            // Inside of a daylight saving time gap, so move towards its end.
+            LocalResult::None => local += Duration::minutes(1),
+        }
+    }
+}
+
+impl<C, T, Tz> AggregationRule<C, T> for CalendarRule<Tz>
+where
+    C: ModularCandle<T>,
+    T: TakerTrade,
+    Tz: TimeZone,
+{
+    fn should_trigger(&mut self, trade: &T, _candle: &C) -> bool {
+        if self.period_end == 0 {
+            self.align(trade.timestamp());
+            return false;
+        }
+
+        // As calendar periods differ in length, align to the period containing the trade,
+        // instead of advancing by a fixed duration.
+        // A trade outside of the range of `DateTime` can't be aligned, so it doesn't trigger.
+        trade.timestamp() >= self.period_end && self.align(trade.timestamp())
+    }
+
+    fn should_trigger_on_time(&mut self, now: i64) -> bool {
+        if self.period_end == 0 || now < self.period_end {
+            return false;
+        }
+
+        self.align(now)
+    }
+}
+
+impl<Tz> TriggerReason for CalendarRule<Tz> {
+    /// The start of the new period, in the unit of the incoming trades
+    type Reason = i64;
+
+    fn trigger_reason(&self) -> i64 {
+        self.period_start
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{FixedOffset, Utc};
+    use chrono_tz::{America::New_York, Europe::Berlin};
+
+    use super::*;
+    use crate::{
+        aggregate_all_trades, candle_components::OpenTimeStamp, plot::OhlcCandle, Aggregator,
+        Candle, GenericAggregator, Trade, H1,
+    };
+
+    #[derive(Default, Debug, Clone, Candle)]
+    struct MonthCandle {
+        open_timestamp: OpenTimeStamp<i64>,
+    }
+
+    fn hms(h: u32, m: u32) -> NaiveTime {
+        NaiveTime::from_hms_opt(h, m, 0).unwrap()
+    }
+
+    fn utc(y: i32, mo: u32, d: u32, h: u32, mi: u32) -> i64 {
+        Utc.with_ymd_and_hms(y, mo, d, h, mi, 0)
+            .unwrap()
+            .timestamp_millis()
+    }
+
+    /// Hourly trades from `start` until `end`
+    fn hourly_trades(start: i64, end: i64) -> Vec<Trade> {
+        (start..end)
+            .step_by(H1.get() as usize)
+            .map(|timestamp| Trade {
+                timestamp,
+                price: 100.0,
+                size: 1.0,
+            })
+            .collect()
+    }
+
+    /// The timestamps of the trades that triggered the rule
+    fn boundaries<Tz: TimeZone>(mut rule: CalendarRule<Tz>, trades: &[Trade]) -> Vec<i64> {
+        let candle = OhlcCandle::default();
+        trades
+            .iter()
+            .filter(|t| rule.should_trigger(*t, &candle))
+            .map(|t| t.timestamp)
+            .collect()
+    }
+
+    #[test]
+    fn calendar_rule_daily_session_across_dst() {
+        // FX days start at 17:00 New York time, which is 22:00 UTC in winter and 21:00 UTC in summer.
+        // Daylight saving time starts on 2024-03-10.
+        let rule = CalendarRule::new(
+            CalendarPeriod::Day,
+            New_York,
+            hms(17, 0),
+            TimestampResolution::Millisecond,
+        );
+        let trades = hourly_trades(utc(2024, 3, 8, 12, 0), utc(2024, 3, 12, 0, 0));
+        assert_eq!(
+            boundaries(rule, &trades),
+            vec![
+                utc(2024, 3, 8, 22, 0),
+                utc(2024, 3, 9, 22, 0),
+                utc(2024, 3, 10, 21, 0),
+                utc(2024, 3, 11, 21, 0),
+            ]
+        );
+    }
+
+    #[test]
+    fn calendar_rule_session_start_in_dst_gap() {
+        // 02:30 does not exist in New York on 2024-03-10, so that day starts at 03:00 EDT.
+        let rule = CalendarRule::new(
+            CalendarPeriod::Day,
+            New_York,
+            hms(2, 30),
+            TimestampResolution::Millisecond,
+        );
+        let trades = hourly_trades(utc(2024, 3, 9, 0, 0), utc(2024, 3, 12, 0, 0));
+        assert_eq!(
+            boundaries(rule, &trades),
+            vec![
+                utc(2024, 3, 9, 8, 0),
+                utc(2024, 3, 10, 7, 0),
+                utc(2024, 3, 11, 7, 0),
+            ]
+        );
+    }
+
+    #[test]
+    fn calendar_rule_session_start_ambiguous() {
+        // 02:30 occurs twice in Berlin on 2024-10-27, the first time at 00:30 UTC.
+        let mut rule = CalendarRule::new(
+            CalendarPeriod::Day,
+            Berlin,
+            hms(2, 30),
+            TimestampResolution::Millisecond,
+        );
+        let trades = hourly_trades(utc(2024, 10, 26, 12, 0), utc(2024, 10, 28, 12, 0));
+        assert_eq!(
+            boundaries(rule.clone(), &trades),
+            vec![utc(2024, 10, 27, 1, 0), utc(2024, 10, 28, 2, 0)]
+        );
+        AggregationRule::<OhlcCandle, Trade>::should_trigger(
+            &mut rule,
+            &trades[14],
+            &OhlcCandle::default(),
+        );
+        assert_eq!(rule.trigger_reason(), utc(2024, 10, 27, 0, 30));
+        assert_eq!(
+            rule.period_start().unwrap().naive_local(),
+            NaiveDate::from_ymd_opt(2024, 10, 27)
+                .unwrap()
+                .and_time(hms(2, 30))
+        );
+    }
+
+    #[test]
+    fn calendar_rule_weekly() {
+        // Weeks starting on Monday at midnight in UTC+2
+        let rule = CalendarRule::new(
+            CalendarPeriod::Week(Weekday::Mon),
+            FixedOffset::east_opt(2 * 3600).unwrap(),
+            hms(0, 0),
+            TimestampResolution::Millisecond,
+        );
+        // 2024-05-01 is a Wednesday
+        let trades = hourly_trades(utc(2024, 5, 1, 0, 0), utc(2024, 5, 22, 0, 0));
+        assert_eq!(
+            boundaries(rule, &trades),
+            vec![
+                utc(2024, 5, 5, 22, 0),
+                utc(2024, 5, 12, 22, 0),
+                utc(2024, 5, 19, 22, 0),
+            ]
+        );
+
+        // FX weeks start on Sunday at 17:00 New York time
+        let rule = CalendarRule::new(
+            CalendarPeriod::Week(Weekday::Sun),
+            New_York,
+            hms(17, 0),
+            TimestampResolution::Millisecond,
+        );
+        assert_eq!(
+            boundaries(rule, &trades),
+            vec![
+                utc(2024, 5, 5, 21, 0),
+                utc(2024, 5, 12, 21, 0),
+                utc(2024, 5, 19, 21, 0),
+            ]
+        );
+    }
+
+    #[test]
+    fn calendar_rule_monthly_and_quarterly() {
+        let trades: Vec<Trade> = (0..400)
+            .map(|day| Trade {
+                timestamp: utc(2024, 1, 1, 12, 0) + day * 86_400_000,
+                price: 100.0,
+                size: 1.0,
+            })
+            .collect();
+
+        let rule = CalendarRule::new(
+            CalendarPeriod::Month,
+            Utc,
+            hms(0, 0),
+            TimestampResolution::Millisecond,
+        );
+        let mut aggregator = GenericAggregator::<MonthCandle, _, Trade>::new(rule, false);
+        let candles = aggregate_all_trades(&trades, &mut aggregator);
+        assert_eq!(candles.len(), 13);
+        assert_eq!(candles[1].open_timestamp(), utc(2024, 2, 1, 12, 0));
+        // 2024 is a leap year
+        assert_eq!(candles[2].open_timestamp(), utc(2024, 3, 1, 12, 0));
+        assert_eq!(candles[12].open_timestamp(), utc(2025, 1, 1, 12, 0));
+
+        let rule = CalendarRule::new(
+            CalendarPeriod::Quarter,
+            Utc,
+            hms(0, 0),
+            TimestampResolution::Millisecond,
+        );
+        assert_eq!(
+            boundaries(rule, &trades),
+            vec![
+                utc(2024, 4, 1, 12, 0),
+                utc(2024, 7, 1, 12, 0),
+                utc(2024, 10, 1, 12, 0),
+                utc(2025, 1, 1, 12, 0),
+            ]
+        );
+    }
+
+    #[test]
+    fn calendar_rule_on_time() {
+        let rule = CalendarRule::new(
+            CalendarPeriod::Day,
+            New_York,
+            hms(17, 0),
+            TimestampResolution::Microsecond,
+        );
+        let mut aggregator = GenericAggregator::<OhlcCandle, _, Trade>::new(rule, false);
+        let trade = Trade {
+            timestamp: utc(2024, 3, 8, 12, 0) * 1_000,
+            price: 100.0,
+            size: 1.0,
+        };
+        assert!(aggregator.update(&trade).is_none());
+        assert!(aggregator
+            .on_time(utc(2024, 3, 8, 21, 59) * 1_000)
+            .is_none());
+        assert!(aggregator.on_time(utc(2024, 3, 8, 22, 0) * 1_000).is_some());
+    }
+}
@@ -0,0 +1,245 @@
+use crate::{ewma::Ewma, AggregationRule, Error, ModularCandle, Result, TakerTrade, TriggerReason};
+
+/// The threshold of the `CusumRule`
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum CusumThreshold {
+    /// A fixed threshold of cumulative log returns, e.g. 0.01 for roughly 1%
+    Fixed(f64),
+
+    /// A multiple of the estimated standard deviation of the log returns between trades,
+    /// which is the square root of the exponentially weighted moving average of the squared log returns
+    Volatility {
+        /// The number of standard deviations the cumulative log returns need to reach
+        multiplier: f64,
+        /// The number of past trades the squared log returns are smoothed over
+        ewma_span: usize,
+        /// The standard deviation used until enough log returns have been observed
+        initial_std_dev: f64,
+    },
+}
+
+/// Creates candles with the symmetric CUSUM filter,
+/// as described by Marcos Lopez de Prado in "Advances in Financial Machine Learning".
+/// The log returns between trades are accumulated separately for upward and downward moves,
+/// with each sum being reset to zero once it turns against its direction:
+/// S+ = max(0, S+ + r), S- = min(0, S- + r).
+/// A candle is finished once either sum reaches the threshold, after which that sum starts over at zero.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CusumRule {
+    threshold: CusumThreshold,
+    // The EWMA of the squared log returns, only used for a volatility scaled threshold
+    variance: Ewma,
+    // The price of the previous trade, None before the first trade
+    prev_price: Option<f64>,
+    pos_sum: f64,
+    neg_sum: f64,
+    // The sum that reached the threshold most recently
+    last_event: f64,
+}
+
+impl CusumRule {
+    /// Create a new instance of the `CusumRule`
+    ///
+    /// # Arguments:
+    /// `threshold`: The positive, fixed or volatility scaled threshold of the cumulative log returns
+    ///
+    pub fn new(threshold: CusumThreshold) -> Result<Self> {
+        let variance = match threshold {
+            CusumThreshold::Fixed(h) => {
+                if !h.is_finite() || h <= 0.0 {
+                    return Err(Error::InvalidParam);
+                }
+                Ewma::new(1, 0.0)
+            }
+            CusumThreshold::Volatility {
+                multiplier,
+                ewma_span,
+                initial_std_dev,
+            } => {
+                if !multiplier.is_finite()
+                    || multiplier <= 0.0
+                    || ewma_span == 0
+                    || !initial_std_dev.is_finite()
+                    || initial_std_dev <= 0.0
+                {
+                    return Err(Error::InvalidParam);
+                }
+                Ewma::new(ewma_span, initial_std_dev * initial_std_dev)
+            }
+        };
+        Ok(Self {
+            threshold,
+            variance,
+            prev_price: None,
+            pos_sum: 0.0,
+            neg_sum: 0.0,
+            last_event: 0.0,
+        })
+    }
+
+    /// The cumulative log return a sum needs to reach before the next candle is finished
+    pub fn current_threshold(&self) -> f64 {
+        match self.threshold {
+            CusumThreshold::Fixed(h) => h,
+            CusumThreshold::Volatility { multiplier, .. } => {
+                multiplier * self.variance.value().sqrt()
+            }
+        }
+    }
+}
+
+impl<C, T> AggregationRule<C, T> for CusumRule
+where
+    C: ModularCandle<T>,
+    T: TakerTrade,
+{
+    fn should_trigger(&mut self, trade: &T, _candle: &C) -> bool {
+        let price = trade.price();
+        // A price without a logarithm would turn the returns and the variance into NaN for good
+        if !price.is_finite() || price <= 0.0 {
+            return false;
+        }
+        let Some(prev_price) = self.prev_price.replace(price) else {
+            return false;
+        };
+        let r = (price / prev_price).ln();
+
+        // The threshold only includes the returns before this trade
+        let threshold = self.current_threshold();
+        if matches!(self.threshold, CusumThreshold::Volatility { .. }) {
+            self.variance.add(r * r);
+        }
+
+        self.pos_sum = (self.pos_sum + r).max(0.0);
+        self.neg_sum = (self.neg_sum + r).min(0.0);
+        if self.pos_sum >= threshold {
+            self.last_event = self.pos_sum;
+            self.pos_sum = 0.0;
+            return true;
+        }
+        if self.neg_sum <= -threshold {
+            self.last_event = self.neg_sum;
+            self.neg_sum = 0.0;
+            return true;
+        }
+
+        false
+    }
+
+    fn reset(&mut self, _trade: &T) {
+        self.pos_sum = 0.0;
+        self.neg_sum = 0.0;
+    }
+}
+
+impl TriggerReason for CusumRule {
+    /// The cumulative log return that reached the threshold,
+    /// positive for an upward move and negative for a downward move
+    type Reason = f64;
+
+    fn trigger_reason(&self) -> f64 {
+        self.last_event
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{plot::OhlcCandle, Trade};
+
+    fn triggers(rule: &mut CusumRule, prices: &[f64]) -> Vec<(usize, f64)> {
+        let candle = OhlcCandle::default();
+        prices
+            .iter()
+            .enumerate()
+            .filter_map(|(i, price)| {
+                let trade = Trade {
+                    timestamp: i as i64,
+                    price: *price,
+                    size: 1.0,
+                };
+                rule.should_trigger(&trade, &candle)
+                    .then(|| (i, rule.trigger_reason()))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn cusum_rule_invalid_params() {
+        assert!(CusumRule::new(CusumThreshold::Fixed(0.0)).is_err());
+        assert!(CusumRule::new(CusumThreshold::Fixed(f64::NAN)).is_err());
+        assert!(CusumRule::new(CusumThreshold::Fixed(f64::INFINITY)).is_err());
+        assert!(CusumRule::new(CusumThreshold::Volatility {
+            multiplier: 2.0,
+            ewma_span: 0,
+            initial_std_dev: 0.01,
+        })
+        .is_err());
+        assert!(CusumRule::new(CusumThreshold::Volatility {
+            multiplier: f64::NAN,
+            ewma_span: 10,
+            initial_std_dev: 0.01,
+        })
+        .is_err());
+        assert!(CusumRule::new(CusumThreshold::Volatility {
+            multiplier: 2.0,
+            ewma_span: 10,
+            initial_std_dev: f64::NAN,
+        })
+        .is_err());
+    }
+
+    #[test]
+    fn cusum_rule_invalid_prices() {
+        for threshold in [
+            CusumThreshold::Fixed(0.02),
+            CusumThreshold::Volatility {
+                multiplier: 2.0,
+                ewma_span: 3,
+                initial_std_dev: 0.01,
+            },
+        ] {
+            let mut rule = CusumRule::new(threshold).unwrap();
+            let prices = [0.0, 100.0, 0.0, -1.0, f64::NAN, f64::INFINITY, 101.0, 102.5];
+            let events = triggers(&mut rule, &prices);
+            // The returns only span the valid prices, starting at 100
+            assert_eq!(events.len(), 1, "{threshold:?}");
+            assert_eq!(events[0].0, 7);
+            assert!((events[0].1 - (102.5_f64 / 100.0).ln()).abs() < 1e-12);
+            assert!(rule.current_threshold().is_finite());
+        }
+    }
+
+    #[test]
+    fn cusum_rule_fixed() {
+        let mut rule = CusumRule::new(CusumThreshold::Fixed(0.02)).unwrap();
+        let events = triggers(&mut rule, &[100.0, 101.0, 102.0, 102.5, 101.0, 100.0]);
+        assert_eq!(
+            events.iter().map(|(i, _)| *i).collect::<Vec<_>>(),
+            vec![3, 5]
+        );
+        assert!((events[0].1 - (102.5_f64 / 100.0).ln()).abs() < 1e-12);
+        assert!((events[1].1 - (100.0_f64 / 102.5).ln()).abs() < 1e-12);
+    }
+
+    #[test]
+    fn cusum_rule_volatility() {
+        // Every trade moves the price up by 1%, so three returns exceed 2.5 standard deviations
+        let prices: Vec<f64> = (0..10).map(|i| 100.0 * 1.01_f64.powi(i)).collect();
+        let mut rule = CusumRule::new(CusumThreshold::Volatility {
+            multiplier: 2.5,
+            ewma_span: 5,
+            initial_std_dev: 0.01,
+        })
+        .unwrap();
+        let events = triggers(&mut rule, &prices);
+        assert_eq!(
+            events.iter().map(|(i, _)| *i).collect::<Vec<_>>(),
+            vec![3, 6, 9]
+        );
+        // The estimated volatility converges to the constant log return
+        assert!((rule.current_threshold() - 2.5 * 1.01_f64.ln()).abs() < 1e-4);
+    }
+}
@@ -0,0 +1,153 @@
+use super::imbalance::ImbalanceState;
+use crate::{AggregationRule, By, Error, ModularCandle, Result, TakerTrade, TriggerReason};
+
+/// Creates dollar imbalance bars as defined by Marcos Lopez de Prado in "Advances in Financial Machine Learning".
+/// Each trade is classified as buy or sell using the tick rule and the signed traded value is accumulated,
+/// which is the notional value defined by the `ContractSpec` of the trade.
+/// So this is the `VolumeImbalanceRule` with the volume measured `By::Notional`.
+/// It takes no `By`, as the traded value already follows the `ContractSpec` for any contract type,
+/// e.g. `size * price` for linear and `size / price` for inverse contracts with a multiplier of 1.
+/// To measure the imbalance `By::Base` or `By::Quote` instead, use the `VolumeImbalanceRule`,
+/// which treats the volume of a trade the same way as the `VolumeRule`.
+/// A candle is finished once the absolute cumulative dollar imbalance reaches the expected threshold,
+/// which is the EWMA of past bar lengths multiplied by the absolute EWMA of the past per-tick dollar imbalances.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DollarImbalanceRule {
+    state: ImbalanceState,
+}
+
+impl DollarImbalanceRule {
+    /// Create a new instance of the `DollarImbalanceRule`
+    ///
+    /// # Arguments:
+    /// `initial_expected_num_ticks`: The expected bar length used until the first bar is finished.
+    /// `initial_expected_imbalance`: The expected dollar imbalance per tick used until the first bar is finished.
+    /// `ewma_span`: The number of past bars the expectations are smoothed over.
+    ///
+    pub fn new(
+        initial_expected_num_ticks: f64,
+        initial_expected_imbalance: f64,
+        ewma_span: usize,
+    ) -> Result<Self> {
+        if !initial_expected_num_ticks.is_finite()
+            || initial_expected_num_ticks <= 0.0
+            || !initial_expected_imbalance.is_finite()
+            || initial_expected_imbalance <= 0.0
+            || ewma_span == 0
+        {
+            return Err(Error::InvalidParam);
+        }
+        Ok(Self {
+            state: ImbalanceState::new(
+                initial_expected_num_ticks,
+                initial_expected_imbalance,
+                ewma_span,
+            ),
+        })
+    }
+
+    /// The absolute dollar imbalance the current bar needs to reach before it is finished
+    pub fn expected_threshold(&self) -> f64 {
+        self.state.expected_threshold()
+    }
+}
+
+impl<C, T> AggregationRule<C, T> for DollarImbalanceRule
+where
+    C: ModularCandle<T>,
+    T: TakerTrade,
+{
+    fn should_trigger(&mut self, trade: &T, _candle: &C) -> bool {
+        self.state
+            .update(trade.price(), By::Notional.volume(trade, trade.size()))
+    }
+
+    fn reset(&mut self, _trade: &T) {
+        self.state.reset();
+    }
+}
+
+impl TriggerReason for DollarImbalanceRule {
+    /// The cumulative signed dollar imbalance of the finished candle
+    type Reason = f64;
+
+    fn trigger_reason(&self) -> f64 {
+        self.state.last_imbalance()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use round::round;
+
+    use super::*;
+    use crate::{
+        aggregate_all_trades,
+        candle_components::tests::{InverseCandle, InverseTrade},
+        load_trades_from_csv,
+        plot::OhlcCandle,
+        GenericAggregator, Trade, VolumeImbalanceRule,
+    };
+
+    #[test]
+    fn dollar_imbalance_rule_invalid_params() {
+        assert!(DollarImbalanceRule::new(0.0, 100.0, 3).is_err());
+        assert!(DollarImbalanceRule::new(3.0, -100.0, 3).is_err());
+        assert!(DollarImbalanceRule::new(3.0, 100.0, 0).is_err());
+        assert!(DollarImbalanceRule::new(f64::NAN, 100.0, 3).is_err());
+        assert!(DollarImbalanceRule::new(3.0, f64::INFINITY, 3).is_err());
+        assert!(DollarImbalanceRule::new(3.0, 100.0, 3).is_ok());
+    }
+
+    #[test]
+    fn dollar_imbalance_rule() {
+        let trades = load_trades_from_csv("data/Bitstamp_BTCEUR_1M.csv").unwrap();
+
+        let mut rule = DollarImbalanceRule::new(3.0, 100.0, 3).unwrap();
+        let candle = OhlcCandle::default();
+        let triggers: Vec<bool> = trades
+            .iter()
+            .map(|t| rule.should_trigger(t, &candle))
+            .collect();
+        assert_eq!(
+            triggers,
+            vec![false, true, false, false, true, true, false, false, false, false, false]
+        );
+        assert_eq!(round(rule.expected_threshold(), 2), 5333.7);
+    }
+
+    #[test]
+    fn dollar_imbalance_rule_aggregation() {
+        let trades = load_trades_from_csv("data/Bitstamp_BTCEUR_1M.csv").unwrap();
+
+        let rule = DollarImbalanceRule::new(3.0, 100.0, 3).unwrap();
+        let mut aggregator = GenericAggregator::<OhlcCandle, _, Trade>::new(rule, false);
+        let candles = aggregate_all_trades(&trades, &mut aggregator);
+        assert_eq!(candles.len(), 3);
+        assert_eq!(candles[0].open(), 27334.25);
+        assert_eq!(candles[1].open(), 27308.91);
+        assert_eq!(candles[2].open(), 27304.72);
+    }
+
+    #[test]
+    fn dollar_imbalance_rule_inverse_contract() {
+        let trades = load_trades_from_csv("data/Bitstamp_BTCEUR_1M.csv").unwrap();
+
+        // The traded value of inverse contracts is in the base currency
+        let mut rule = DollarImbalanceRule::new(3.0, 1e-5, 3).unwrap();
+        let mut reference = VolumeImbalanceRule::new(3.0, 1e-5, 3, By::Notional).unwrap();
+        let candle = InverseCandle::default();
+        let mut num_triggers = 0;
+        for t in trades.iter() {
+            let t = InverseTrade(*t);
+            let triggered = rule.should_trigger(&t, &candle);
+            assert_eq!(triggered, reference.should_trigger(&t, &candle));
+            assert_eq!(rule.trigger_reason(), reference.trigger_reason());
+            num_triggers += triggered as usize;
+        }
+        assert!(num_triggers > 0);
+        // Compared to 5333.7 EUR for linear contracts
+        assert_eq!(round(rule.expected_threshold(), 8), 0.00071621);
+    }
+}
@@ -0,0 +1,136 @@
+use super::run::RunState;
+use crate::{AggregationRule, By, Error, ModularCandle, Result, TakerTrade, TriggerReason};
+
+/// Creates dollar run bars as defined by Marcos Lopez de Prado in "Advances in Financial Machine Learning".
+/// The side of each trade is taken from the sign of its size and the traded value,
+/// which is the notional value defined by the `ContractSpec` of the trade,
+/// of buys and sells is accumulated separately.
+/// A candle is finished once the larger value reaches the expected run,
+/// which is the EWMA of past bar lengths multiplied by the larger expected one-sided value per tick.
+/// So this is the `VolumeRunRule` with the volume measured `By::Notional`.
+/// It takes no `By`, as the traded value already follows the `ContractSpec` for any contract type,
+/// e.g. `size * price` for linear and `size / price` for inverse contracts with a multiplier of 1.
+/// To measure the runs `By::Base` or `By::Quote` instead, use the `VolumeRunRule`,
+/// which treats the volume of a trade the same way as the `VolumeRule`.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DollarRunRule {
+    state: RunState,
+}
+
+impl DollarRunRule {
+    /// Create a new instance of the `DollarRunRule`
+    ///
+    /// # Arguments:
+    /// `initial_expected_num_ticks`: The expected bar length used until the first bar is finished.
+    /// `initial_expected_buy_proportion`: The expected fraction of buys used until the first bar is finished, in range [0, 1].
+    /// `initial_expected_value`: The expected traded value of a single trade used until the first bar is finished.
+    /// `ewma_span`: The number of past bars the expectations are smoothed over.
+    ///
+    pub fn new(
+        initial_expected_num_ticks: f64,
+        initial_expected_buy_proportion: f64,
+        initial_expected_value: f64,
+        ewma_span: usize,
+    ) -> Result<Self> {
+        if !initial_expected_num_ticks.is_finite()
+            || initial_expected_num_ticks <= 0.0
+            || !(0.0..=1.0).contains(&initial_expected_buy_proportion)
+            || !initial_expected_value.is_finite()
+            || initial_expected_value <= 0.0
+            || ewma_span == 0
+        {
+            return Err(Error::InvalidParam);
+        }
+        Ok(Self {
+            state: RunState::new(
+                initial_expected_num_ticks,
+                initial_expected_buy_proportion,
+                initial_expected_value,
+                ewma_span,
+            ),
+        })
+    }
+
+    /// The one-sided traded value the current bar needs to reach before it is finished
+    pub fn expected_threshold(&self) -> f64 {
+        self.state.expected_threshold()
+    }
+}
+
+impl<C, T> AggregationRule<C, T> for DollarRunRule
+where
+    C: ModularCandle<T>,
+    T: TakerTrade,
+{
+    fn should_trigger(&mut self, trade: &T, _candle: &C) -> bool {
+        self.state
+            .update(trade.size() > 0.0, By::Notional.volume(trade, trade.size()))
+    }
+
+    fn reset(&mut self, _trade: &T) {
+        self.state.reset();
+    }
+}
+
+impl TriggerReason for DollarRunRule {
+    /// The one-sided traded value of the finished candle
+    type Reason = f64;
+
+    fn trigger_reason(&self) -> f64 {
+        self.state.last_run()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use round::round;
+
+    use super::*;
+    use crate::{load_trades_from_csv, plot::OhlcCandle, VolumeRunRule};
+
+    #[test]
+    fn dollar_run_rule_invalid_params() {
+        assert!(DollarRunRule::new(0.0, 0.5, 200.0, 3).is_err());
+        assert!(DollarRunRule::new(4.0, -0.5, 200.0, 3).is_err());
+        assert!(DollarRunRule::new(4.0, 0.5, -200.0, 3).is_err());
+        assert!(DollarRunRule::new(4.0, 0.5, 200.0, 0).is_err());
+        assert!(DollarRunRule::new(f64::INFINITY, 0.5, 200.0, 3).is_err());
+        assert!(DollarRunRule::new(4.0, 0.5, f64::NAN, 3).is_err());
+        assert!(DollarRunRule::new(4.0, 0.5, 200.0, 3).is_ok());
+    }
+
+    #[test]
+    fn dollar_run_rule() {
+        let trades = load_trades_from_csv("data/Bitstamp_BTCEUR_1M.csv").unwrap();
+
+        let mut rule = DollarRunRule::new(4.0, 0.5, 200.0, 3).unwrap();
+        let candle = OhlcCandle::default();
+        let triggers: Vec<bool> = trades
+            .iter()
+            .map(|t| rule.should_trigger(t, &candle))
+            .collect();
+        assert_eq!(
+            triggers,
+            vec![false, true, false, false, true, true, false, false, false, false, false]
+        );
+        assert_eq!(round(rule.expected_threshold(), 2), 5438.72);
+    }
+
+    #[test]
+    fn dollar_run_rule_matches_notional_volume_run_rule() {
+        let trades = load_trades_from_csv("data/Bitstamp_BTCEUR_1M.csv").unwrap();
+
+        let mut rule = DollarRunRule::new(4.0, 0.5, 200.0, 3).unwrap();
+        let mut reference = VolumeRunRule::new(4.0, 0.5, 200.0, 3, By::Notional).unwrap();
+        let candle = OhlcCandle::default();
+        for t in trades.iter() {
+            assert_eq!(
+                rule.should_trigger(t, &candle),
+                reference.should_trigger(t, &candle)
+            );
+            assert_eq!(rule.trigger_reason(), reference.trigger_reason());
+        }
+        assert_eq!(rule.expected_threshold(), reference.expected_threshold());
+    }
+}
@@ -0,0 +1,19 @@
+/// Implemented by time based rules, allowing an aggregator to emit a candle for every period,
+/// even if no trades arrived during it.
+/// See `GenericAggregator::update_filling_gaps`.
+pub trait GapFill {
+    /// Called after the rule triggered on a trade with the given timestamp.
+    /// If the trade lies beyond the current period, that period did not contain any trades.
+    /// In that case the rule advances to the next period.
+    ///
+    /// # Returns:
+    /// The open and close timestamp of the empty period, in the unit of the trade timestamps,
+    /// or None once the current period contains the timestamp.
+    fn next_empty_period(&mut self, timestamp: i64) -> Option<(i64, i64)>;
+
+    /// Called before the first trade after the candle has been finished by `should_trigger_on_time`.
+    /// Moves the rule back to the period of that candle, as if it had not been finished on time,
+    /// so the trade triggers the rule and the empty periods in between are filled,
+    /// even though the rule already advanced to the period containing the time of `should_trigger_on_time`.
+    fn undo_on_time(&mut self);
+}
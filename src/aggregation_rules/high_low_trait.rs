@@ -0,0 +1,10 @@
+/// Exposes the extreme prices of a candle to an `AggregationRule`, e.g. for the `RangeRule`.
+/// Implemented by the `Candle` macro for every candle containing the `High` and `Low` components,
+/// just like `Ohlc` for candles that also contain the `Open` and `Close` components.
+pub trait HighLow {
+    /// The highest price of the candle
+    fn highest(&self) -> f64;
+
+    /// The lowest price of the candle
+    fn lowest(&self) -> f64;
+}
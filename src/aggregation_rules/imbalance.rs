@@ -0,0 +1,79 @@
+use super::tick_sign::TickSign;
+use crate::ewma::Ewma;
+
+/// The shared state of the imbalance bars as defined by Marcos Lopez de Prado.
+/// Each trade is classified with the tick rule and its signed weight is accumulated.
+/// A bar is finished once the absolute cumulative imbalance reaches
+/// the EWMA of past bar lengths multiplied by the absolute EWMA of the past per-tick imbalances:
+/// |theta_T| >= E[T] * |E[b_t * v_t]|
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub(crate) struct ImbalanceState {
+    tick_sign: TickSign,
+
+    // The expected number of ticks in a bar
+    expected_num_ticks: Ewma,
+
+    // The expected signed imbalance per tick
+    expected_imbalance: Ewma,
+
+    // The number of ticks of the current bar
+    num_ticks: usize,
+
+    // The cumulative signed imbalance of the current bar
+    imbalance: f64,
+
+    // The cumulative signed imbalance of the most recently finished bar
+    last_imbalance: f64,
+}
+
+impl ImbalanceState {
+    pub(crate) fn new(
+        initial_expected_num_ticks: f64,
+        initial_expected_imbalance: f64,
+        ewma_span: usize,
+    ) -> Self {
+        Self {
+            tick_sign: TickSign::default(),
+            expected_num_ticks: Ewma::new(ewma_span, initial_expected_num_ticks),
+            expected_imbalance: Ewma::new(ewma_span, initial_expected_imbalance),
+            num_ticks: 0,
+            imbalance: 0.0,
+            last_imbalance: 0.0,
+        }
+    }
+
+    /// The absolute imbalance the current bar needs to reach before it is finished
+    pub(crate) fn expected_threshold(&self) -> f64 {
+        self.expected_num_ticks.value() * self.expected_imbalance.value().abs()
+    }
+
+    /// The cumulative signed imbalance of the most recently finished bar
+    pub(crate) fn last_imbalance(&self) -> f64 {
+        self.last_imbalance
+    }
+
+    /// Starts a new bar without updating the expectations
+    pub(crate) fn reset(&mut self) {
+        self.num_ticks = 0;
+        self.imbalance = 0.0;
+    }
+
+    /// Accumulates the trade with the given unsigned `weight`,
+    /// returning true if the bar is finished
+    pub(crate) fn update(&mut self, price: f64, weight: f64) -> bool {
+        self.num_ticks += 1;
+        self.imbalance += self.tick_sign.update(price) * weight;
+
+        if self.imbalance.abs() >= self.expected_threshold() {
+            let num_ticks = self.num_ticks as f64;
+            self.expected_num_ticks.add(num_ticks);
+            self.expected_imbalance.add(self.imbalance / num_ticks);
+            self.last_imbalance = self.imbalance;
+
+            self.reset();
+            return true;
+        }
+        false
+    }
+}
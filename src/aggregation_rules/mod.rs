@@ -1,13 +1,62 @@
+mod adaptive_relative_price_rule;
+mod adaptive_threshold;
+mod adaptive_tick_rule;
+mod adaptive_volume_rule;
 mod aggregation_rule_trait;
 mod aligned_time_rule;
+mod all_rule;
+mod any_rule;
+#[cfg(feature = "chrono")]
+mod calendar_rule;
+mod cusum_rule;
+mod dollar_imbalance_rule;
+mod dollar_run_rule;
+mod gap_fill_trait;
+mod high_low_trait;
+mod imbalance;
+mod range_rule;
 mod relative_price_rule;
+mod run;
+#[cfg(feature = "chrono")]
+mod session_rule;
+mod tick_imbalance_rule;
 mod tick_rule;
+mod tick_run_rule;
+mod tick_sign;
 mod time_rule;
+mod trigger_reason_trait;
+mod triggered_by;
+mod volume_imbalance_rule;
 mod volume_rule;
+mod volume_run_rule;
+mod volume_split_trait;
 
+pub use adaptive_relative_price_rule::AdaptiveRelativePriceRule;
+pub use adaptive_tick_rule::AdaptiveTickRule;
+pub use adaptive_volume_rule::AdaptiveVolumeRule;
 pub use aggregation_rule_trait::AggregationRule;
 pub use aligned_time_rule::*;
+pub use all_rule::AllRule;
+pub use any_rule::AnyRule;
+#[cfg(feature = "chrono")]
+pub use calendar_rule::{CalendarPeriod, CalendarRule};
+pub use cusum_rule::{CusumRule, CusumThreshold};
+pub use dollar_imbalance_rule::DollarImbalanceRule;
+pub use dollar_run_rule::DollarRunRule;
+pub use gap_fill_trait::GapFill;
+pub use high_low_trait::HighLow;
+pub use range_rule::RangeRule;
 pub use relative_price_rule::RelativePriceRule;
+#[cfg(feature = "chrono")]
+pub use session_rule::{Session, SessionRule, SessionSchedule};
+pub use tick_imbalance_rule::TickImbalanceRule;
 pub use tick_rule::TickRule;
+pub use tick_run_rule::TickRunRule;
 pub use time_rule::*;
+pub use trigger_reason_trait::TriggerReason;
+pub use triggered_by::{CombinedReason, TriggeredBy};
+pub use volume_imbalance_rule::VolumeImbalanceRule;
 pub use volume_rule::VolumeRule;
+pub use volume_run_rule::VolumeRunRule;
+pub use volume_split_trait::VolumeSplit;
+pub(crate) use volume_split_trait::SPLIT_EPSILON;
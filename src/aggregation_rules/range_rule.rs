@@ -0,0 +1,167 @@
+use crate::{AggregationRule, Error, HighLow, ModularCandle, Result, TakerTrade, TriggerReason};
+
+// Relative tolerance for ranges that exactly match the threshold, e.g. a number of ticks
+const RANGE_EPSILON: f64 = 1e-9;
+
+/// Creates range bars, finishing a candle once the spread between its high and low price
+/// reaches a fixed absolute price range, e.g. a number of ticks.
+/// The range includes the price of the most recent trade,
+/// so the trade reaching the range is only part of the finished candle
+/// if `include_trade_that_triggered_rule` is set on the aggregator.
+/// Requires the candle to implement `HighLow`,
+/// which the `Candle` macro does for candles containing the `High` and `Low` components.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RangeRule {
+    range: f64,
+    // The most recently observed range, including the most recent trade
+    current_range: f64,
+}
+
+impl RangeRule {
+    /// Create a new instance
+    ///
+    /// # Arguments:
+    /// `range`: The absolute price range between the high and low of a candle, which finishes it
+    ///
+    pub fn new(range: f64) -> Result<Self> {
+        if !range.is_finite() || range <= 0.0 {
+            return Err(Error::InvalidParam);
+        }
+        Ok(Self {
+            range,
+            current_range: 0.0,
+        })
+    }
+
+    /// Create a new instance with a range of a number of ticks
+    ///
+    /// # Arguments:
+    /// `n_ticks`: The number of ticks between the high and low of a candle, which finishes it
+    /// `tick_size`: The minimum price increment of the instrument
+    ///
+    pub fn from_ticks(n_ticks: u32, tick_size: f64) -> Result<Self> {
+        if n_ticks == 0 || !tick_size.is_finite() || tick_size <= 0.0 {
+            return Err(Error::InvalidParam);
+        }
+        Self::new(n_ticks as f64 * tick_size)
+    }
+}
+
+impl<C, T> AggregationRule<C, T> for RangeRule
+where
+    C: ModularCandle<T> + HighLow,
+    T: TakerTrade,
+{
+    fn should_trigger(&mut self, trade: &T, candle: &C) -> bool {
+        // The candle does not contain the trade yet. The extremes of an empty candle are ignored,
+        // as they are initialized such that any price replaces them.
+        let high = candle.highest().max(trade.price());
+        let low = candle.lowest().min(trade.price());
+        self.current_range = high - low;
+
+        self.current_range >= self.range * (1.0 - RANGE_EPSILON)
+    }
+}
+
+impl TriggerReason for RangeRule {
+    /// The range including the most recent trade
+    type Reason = f64;
+
+    fn trigger_reason(&self) -> f64 {
+        self.current_range
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        aggregate_all_trades, candle_components::tests::TRADES, plot::OhlcCandle,
+        GenericAggregator, Trade,
+    };
+
+    #[test]
+    fn range_rule_invalid_params() {
+        assert!(RangeRule::new(-1.0).is_err());
+        assert!(RangeRule::new(f64::NAN).is_err());
+        assert!(RangeRule::new(f64::INFINITY).is_err());
+        assert!(RangeRule::from_ticks(3, 0.0).is_err());
+        assert!(RangeRule::from_ticks(3, f64::NAN).is_err());
+        assert!(RangeRule::from_ticks(3, f64::INFINITY).is_err());
+    }
+
+    #[test]
+    fn range_rule() {
+        assert!(RangeRule::new(0.0).is_err());
+        assert!(RangeRule::from_ticks(0, 0.5).is_err());
+
+        // Prices: 100, 101, 100, 102, 103, 104, 102, 101, 102, 105
+        let rule = RangeRule::from_ticks(3, 0.5).unwrap();
+        let mut aggregator = GenericAggregator::<OhlcCandle, _, Trade>::new(rule, false);
+        let candles = aggregate_all_trades(&TRADES, &mut aggregator);
+        assert_eq!(
+            candles
+                .iter()
+                .map(|c| (c.open(), c.close()))
+                .collect::<Vec<_>>(),
+            vec![
+                (100.0, 100.0),
+                (102.0, 103.0),
+                (104.0, 104.0),
+                (102.0, 102.0)
+            ]
+        );
+        assert_eq!(aggregator.aggregation_rule().trigger_reason(), 4.0);
+
+        // Including the triggering trade, every candle spans at least the range
+        let rule = RangeRule::new(1.5).unwrap();
+        let mut aggregator = GenericAggregator::<OhlcCandle, _, Trade>::new(rule, true);
+        let candles = aggregate_all_trades(&TRADES, &mut aggregator);
+        assert_eq!(candles.len(), 4);
+        assert!(candles.iter().all(|c| c.high() - c.low() >= 1.5));
+    }
+
+    #[test]
+    fn range_rule_high_low_candle() {
+        use trade_aggregation_derive::Candle;
+
+        use crate::candle_components::{self, High, NumTrades};
+
+        // The `Candle` macro implements `HighLow` without the open and close components,
+        // no matter how they are imported
+        #[derive(Debug, Default, Clone, Candle)]
+        struct HighLowCandle {
+            high: High,
+            low: candle_components::Low,
+            num_trades: NumTrades<u32>,
+        }
+
+        let rule = RangeRule::from_ticks(3, 0.5).unwrap();
+        let mut aggregator = GenericAggregator::<HighLowCandle, _, Trade>::new(rule, false);
+        let candles = aggregate_all_trades(&TRADES, &mut aggregator);
+        assert_eq!(
+            candles.iter().map(|c| c.num_trades()).collect::<Vec<_>>(),
+            vec![3, 2, 1, 3]
+        );
+        assert_eq!(candles[1].highest(), 103.0);
+        assert_eq!(candles[1].lowest(), 102.0);
+    }
+
+    #[test]
+    fn range_rule_ticks() {
+        // 100.0 to 100.3 is slightly less than 3 times 0.1 in floating point
+        let mut rule = RangeRule::from_ticks(3, 0.1).unwrap();
+        let mut candle = OhlcCandle::default();
+        let trade = |price| Trade {
+            timestamp: 0,
+            price,
+            size: 1.0,
+        };
+        for price in [100.0, 100.2, 100.1] {
+            assert!(!rule.should_trigger(&trade(price), &candle));
+            candle.update(&trade(price));
+        }
+        assert!(rule.should_trigger(&trade(100.3), &candle));
+    }
+}
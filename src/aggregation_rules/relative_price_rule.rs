@@ -1,10 +1,13 @@
-use crate::{AggregationRule, Error, ModularCandle, Result, TakerTrade};
+use crate::{AggregationRule, Error, ModularCandle, Result, TakerTrade, TriggerReason};
 
 /// Creates Candles once the price changed by a give relative absolute price delta
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct RelativePriceRule {
     init: bool,
     init_price: f64,
+    // The most recently observed relative price change
+    price_delta: f64,
     threshold_fraction: f64,
 }
 
@@ -21,6 +24,7 @@ impl RelativePriceRule {
         Ok(Self {
             init: true,
             init_price: 0.0,
+            price_delta: 0.0,
             threshold_fraction,
         })
     }
@@ -38,14 +42,28 @@ where
             return false;
         }
 
-        let price_delta = (trade.price() - self.init_price).abs() / self.init_price;
+        self.price_delta = (trade.price() - self.init_price).abs() / self.init_price;
 
-        if price_delta >= self.threshold_fraction {
+        if self.price_delta >= self.threshold_fraction {
             self.init_price = trade.price();
             return true;
         }
         false
     }
+
+    fn reset(&mut self, trade: &T) {
+        self.init = false;
+        self.init_price = trade.price();
+    }
+}
+
+impl TriggerReason for RelativePriceRule {
+    /// The relative price change since the last candle
+    type Reason = f64;
+
+    fn trigger_reason(&self) -> f64 {
+        self.price_delta
+    }
 }
 
 #[cfg(test)]
@@ -61,61 +79,46 @@ mod tests {
     fn relative_price_rule() {
         let mut rule = RelativePriceRule::new(0.01).unwrap();
 
-        assert_eq!(
-            rule.should_trigger(
-                &Trade {
-                    timestamp: 0,
-                    price: 100.0,
-                    size: 10.0
-                },
-                &OhlcCandle::default()
-            ),
-            false
-        );
-        assert_eq!(
-            rule.should_trigger(
-                &Trade {
-                    timestamp: 0,
-                    price: 100.5,
-                    size: 10.0
-                },
-                &OhlcCandle::default()
-            ),
-            false
-        );
-        assert_eq!(
-            rule.should_trigger(
-                &Trade {
-                    timestamp: 0,
-                    price: 101.0,
-                    size: 10.0
-                },
-                &OhlcCandle::default()
-            ),
-            true
-        );
-        assert_eq!(
-            rule.should_trigger(
-                &Trade {
-                    timestamp: 0,
-                    price: 100.5,
-                    size: 10.0
-                },
-                &OhlcCandle::default()
-            ),
-            false
-        );
-        assert_eq!(
-            rule.should_trigger(
-                &Trade {
-                    timestamp: 0,
-                    price: 99.0,
-                    size: 10.0
-                },
-                &OhlcCandle::default()
-            ),
-            true
-        );
+        assert!(!rule.should_trigger(
+            &Trade {
+                timestamp: 0,
+                price: 100.0,
+                size: 10.0
+            },
+            &OhlcCandle::default()
+        ));
+        assert!(!rule.should_trigger(
+            &Trade {
+                timestamp: 0,
+                price: 100.5,
+                size: 10.0
+            },
+            &OhlcCandle::default()
+        ));
+        assert!(rule.should_trigger(
+            &Trade {
+                timestamp: 0,
+                price: 101.0,
+                size: 10.0
+            },
+            &OhlcCandle::default()
+        ));
+        assert!(!rule.should_trigger(
+            &Trade {
+                timestamp: 0,
+                price: 100.5,
+                size: 10.0
+            },
+            &OhlcCandle::default()
+        ));
+        assert!(rule.should_trigger(
+            &Trade {
+                timestamp: 0,
+                price: 99.0,
+                size: 10.0
+            },
+            &OhlcCandle::default()
+        ));
     }
 
     #[test]
@@ -0,0 +1,111 @@
+use crate::ewma::Ewma;
+
+/// The shared state of the run bars as defined by Marcos Lopez de Prado.
+/// The weights of buys and sells are accumulated separately and
+/// a bar is finished once the larger one reaches the expected run:
+/// max(sum(v_buy), sum(v_sell)) >= E[T] * max(P[b = 1] * E[v | b = 1], (1 - P[b = 1]) * E[v | b = -1])
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub(crate) struct RunState {
+    // The expected number of ticks in a bar
+    expected_num_ticks: Ewma,
+
+    // The expected probability of a tick being a buy
+    expected_buy_proportion: Ewma,
+
+    // The expected weight of a buy
+    expected_buy_weight: Ewma,
+
+    // The expected weight of a sell
+    expected_sell_weight: Ewma,
+
+    // The number of ticks of the current bar
+    num_ticks: usize,
+
+    // The number of buys of the current bar
+    num_buys: usize,
+
+    // The cumulative weight of the buys of the current bar
+    buy_weight: f64,
+
+    // The cumulative weight of the sells of the current bar
+    sell_weight: f64,
+
+    // The run of the most recently finished bar
+    last_run: f64,
+}
+
+impl RunState {
+    pub(crate) fn new(
+        initial_expected_num_ticks: f64,
+        initial_expected_buy_proportion: f64,
+        initial_expected_weight: f64,
+        ewma_span: usize,
+    ) -> Self {
+        Self {
+            expected_num_ticks: Ewma::new(ewma_span, initial_expected_num_ticks),
+            expected_buy_proportion: Ewma::new(ewma_span, initial_expected_buy_proportion),
+            expected_buy_weight: Ewma::new(ewma_span, initial_expected_weight),
+            expected_sell_weight: Ewma::new(ewma_span, initial_expected_weight),
+            num_ticks: 0,
+            num_buys: 0,
+            buy_weight: 0.0,
+            sell_weight: 0.0,
+            last_run: 0.0,
+        }
+    }
+
+    /// The run the current bar needs to reach before it is finished
+    pub(crate) fn expected_threshold(&self) -> f64 {
+        let p_buy = self.expected_buy_proportion.value();
+        let buy_run = p_buy * self.expected_buy_weight.value();
+        let sell_run = (1.0 - p_buy) * self.expected_sell_weight.value();
+
+        self.expected_num_ticks.value() * buy_run.max(sell_run)
+    }
+
+    /// The run of the most recently finished bar
+    pub(crate) fn last_run(&self) -> f64 {
+        self.last_run
+    }
+
+    /// Starts a new bar without updating the expectations
+    pub(crate) fn reset(&mut self) {
+        self.num_ticks = 0;
+        self.num_buys = 0;
+        self.buy_weight = 0.0;
+        self.sell_weight = 0.0;
+    }
+
+    /// Accumulates the trade with the given unsigned `weight` on the side given by `is_buy`,
+    /// returning true if the bar is finished
+    pub(crate) fn update(&mut self, is_buy: bool, weight: f64) -> bool {
+        self.num_ticks += 1;
+        if is_buy {
+            self.num_buys += 1;
+            self.buy_weight += weight;
+        } else {
+            self.sell_weight += weight;
+        }
+
+        if self.buy_weight.max(self.sell_weight) >= self.expected_threshold() {
+            let num_sells = self.num_ticks - self.num_buys;
+            self.last_run = self.buy_weight.max(self.sell_weight);
+            self.expected_num_ticks.add(self.num_ticks as f64);
+            self.expected_buy_proportion
+                .add(self.num_buys as f64 / self.num_ticks as f64);
+            if self.num_buys > 0 {
+                self.expected_buy_weight
+                    .add(self.buy_weight / self.num_buys as f64);
+            }
+            if num_sells > 0 {
+                self.expected_sell_weight
+                    .add(self.sell_weight / num_sells as f64);
+            }
+
+            self.reset();
+            return true;
+        }
+        false
+    }
+}
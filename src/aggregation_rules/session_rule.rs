@@ -0,0 +1,470 @@
+use chrono::{Datelike, Duration, NaiveTime, TimeZone, Weekday};
+
+use super::calendar_rule::resolve_local;
+use crate::{
+    AggregationRule, Error, ModularCandle, Result, TakerTrade, TimestampResolution, TriggerReason,
+};
+
+/// A recurring trading session, e.g. the regular trading hours of an exchange
+/// from 09:30 until 16:00 local time on weekdays.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Session {
+    name: String,
+    start: NaiveTime,
+    end: NaiveTime,
+    weekdays: Vec<Weekday>,
+}
+
+impl Session {
+    /// Create a new session
+    ///
+    /// # Arguments:
+    /// `name`: The name of the session, e.g. "pre-market"
+    /// `start`: The local time at which the session opens
+    /// `end`: The local time at which the session closes, exclusive.
+    ///     If it is before the start, the session closes on the next day, e.g. 18:00 until 17:00 for futures.
+    /// `weekdays`: The days on which the session opens
+    ///
+    pub fn new(
+        name: impl Into<String>,
+        start: NaiveTime,
+        end: NaiveTime,
+        weekdays: &[Weekday],
+    ) -> Result<Self> {
+        if start == end || weekdays.is_empty() {
+            return Err(Error::InvalidParam);
+        }
+
+        Ok(Self {
+            name: name.into(),
+            start,
+            end,
+            weekdays: weekdays.to_vec(),
+        })
+    }
+
+    /// The name of the session
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+/// The trading sessions of a market in its local time zone,
+/// e.g. pre-market and regular trading hours in New York.
+/// Sessions must not overlap.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SessionSchedule<Tz> {
+    timezone: Tz,
+    sessions: Vec<Session>,
+}
+
+impl<Tz: TimeZone> SessionSchedule<Tz> {
+    /// Create a new session schedule
+    ///
+    /// # Arguments:
+    /// `timezone`: The time zone in which the session times are given,
+    ///     e.g. `chrono_tz::America::New_York`
+    /// `sessions`: The sessions, of which there must be at least one
+    ///
+    pub fn new(timezone: Tz, sessions: Vec<Session>) -> Result<Self> {
+        if sessions.is_empty() {
+            return Err(Error::InvalidParam);
+        }
+
+        Ok(Self { timezone, sessions })
+    }
+
+    /// The sessions of the schedule
+    pub fn sessions(&self) -> &[Session] {
+        &self.sessions
+    }
+
+    /// The segment of the schedule containing the timestamp.
+    /// A timestamp outside of the range of `DateTime` is outside of all sessions.
+    fn segment_at(&self, timestamp: i64, res: TimestampResolution) -> Segment {
+        let Some(utc) = res.datetime_from_timestamp(timestamp) else {
+            // Only covers the timestamp itself, so the next trade is placed on the schedule again
+            return Segment::Break {
+                end: timestamp.saturating_add(1),
+            };
+        };
+        let date = utc.with_timezone(&self.timezone).date_naive();
+
+        let mut next_start = i64::MAX;
+        // A session closing on the next day may have opened on the previous day.
+        // As every session opens at least once a week, the next one is found within a week.
+        for offset in -1..=7 {
+            let day = date + Duration::days(offset);
+            for (index, session) in self.sessions.iter().enumerate() {
+                if !session.weekdays.contains(&day.weekday()) {
+                    continue;
+                }
+                let start =
+                    res.timestamp_from_datetime(&resolve_local(&self.timezone, day, session.start));
+                if start > timestamp {
+                    next_start = next_start.min(start);
+                    continue;
+                }
+                let end_day = if session.end < session.start {
+                    day + Duration::days(1)
+                } else {
+                    day
+                };
+                let end = res.timestamp_from_datetime(&resolve_local(
+                    &self.timezone,
+                    end_day,
+                    session.end,
+                ));
+                if timestamp < end {
+                    return Segment::Session { index, start, end };
+                }
+            }
+            // Sessions opening on a later day can't open any earlier.
+            if offset >= 0 && next_start != i64::MAX {
+                break;
+            }
+        }
+
+        Segment::Break { end: next_start }
+    }
+}
+
+/// A section of time, either inside of a session or in between sessions,
+/// with the timestamps in the unit of the incoming trades
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+enum Segment {
+    Session { index: usize, start: i64, end: i64 },
+    // Until the next session opens
+    Break { end: i64 },
+}
+
+impl Segment {
+    fn end(&self) -> i64 {
+        match self {
+            Segment::Session { end, .. } | Segment::Break { end } => *end,
+        }
+    }
+
+    /// Assumes the timestamps to be increasing
+    fn contains(&self, timestamp: i64) -> bool {
+        match self {
+            Segment::Session { start, end, .. } => *start <= timestamp && timestamp < *end,
+            Segment::Break { end } => timestamp < *end,
+        }
+    }
+}
+
+/// Wraps any `AggregationRule`, such as a `TimeRule` or `AlignedTimeRule`,
+/// so that candles never span the boundary of a trading session,
+/// e.g. a 30 minute candle must not contain the overnight close,
+/// and pre-market and regular trading hours end up in separate candles.
+/// A candle is finished at the end of each session and the wrapped rule is reset at the session open,
+/// so it starts a fresh period with the first trade of the session.
+/// Trades outside of all sessions are either dropped, or aggregated into separate candles
+/// by the wrapped rule in between sessions.
+/// Trades with a timestamp outside of the range of `chrono::DateTime` are outside of all sessions.
+///
+/// Use `Aggregator::on_time` to finish the last candle of a session at its end,
+/// instead of with the first trade of the next one.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SessionRule<R, Tz> {
+    rule: R,
+    schedule: SessionSchedule<Tz>,
+    drop_outside_sessions: bool,
+    trade_timestamp_resolution: TimestampResolution,
+
+    // The segment of the current candle, None until the first trade of a segment has been observed
+    current: Option<Segment>,
+    // The segment of the most recent trade, to avoid time zone conversions for every trade
+    latest: Option<Segment>,
+    // Set when the most recent candle was finished by a session boundary
+    session_changed: bool,
+}
+
+impl<R, Tz: TimeZone> SessionRule<R, Tz> {
+    /// Create a new instance of the session rule
+    ///
+    /// # Arguments:
+    /// `rule`: The rule creating candles within a session
+    /// `schedule`: The trading sessions
+    /// `drop_outside_sessions`: If true, trades outside of all sessions are ignored by the aggregator
+    /// `trade_timestamp_resolution`: The resolution each Trade timestamp will have
+    ///
+    pub fn new(
+        rule: R,
+        schedule: SessionSchedule<Tz>,
+        drop_outside_sessions: bool,
+        trade_timestamp_resolution: TimestampResolution,
+    ) -> Self {
+        Self {
+            rule,
+            schedule,
+            drop_outside_sessions,
+            trade_timestamp_resolution,
+            current: None,
+            latest: None,
+            session_changed: false,
+        }
+    }
+
+    /// Get a reference to the wrapped rule
+    pub fn rule(&self) -> &R {
+        &self.rule
+    }
+
+    /// Get a reference to the session schedule
+    pub fn schedule(&self) -> &SessionSchedule<Tz> {
+        &self.schedule
+    }
+
+    /// The session of the current candle,
+    /// or None if the candle is outside of all sessions or no trade has been observed yet
+    pub fn current_session(&self) -> Option<&Session> {
+        match self.current? {
+            Segment::Session { index, .. } => Some(&self.schedule.sessions[index]),
+            Segment::Break { .. } => None,
+        }
+    }
+
+    fn segment_of(&mut self, timestamp: i64) -> Segment {
+        if let Some(segment) = self.latest.filter(|s| s.contains(timestamp)) {
+            return segment;
+        }
+        let segment = self
+            .schedule
+            .segment_at(timestamp, self.trade_timestamp_resolution);
+        self.latest = Some(segment);
+
+        segment
+    }
+}
+
+impl<R, C, T, Tz> AggregationRule<C, T> for SessionRule<R, Tz>
+where
+    R: AggregationRule<C, T>,
+    C: ModularCandle<T>,
+    T: TakerTrade,
+    Tz: TimeZone,
+{
+    fn should_trigger(&mut self, trade: &T, candle: &C) -> bool {
+        let segment = self.segment_of(trade.timestamp());
+        if self.current != Some(segment) {
+            let first = self.current.is_none();
+            self.current = Some(segment);
+            self.session_changed = !first;
+            self.rule.reset(trade);
+            return !first;
+        }
+
+        self.session_changed = false;
+        self.rule.should_trigger(trade, candle)
+    }
+
+    fn reset(&mut self, trade: &T) {
+        self.current = Some(self.segment_of(trade.timestamp()));
+        self.session_changed = false;
+        self.rule.reset(trade);
+    }
+
+    fn should_trigger_on_time(&mut self, now: i64) -> bool {
+        let Some(segment) = self.current else {
+            return false;
+        };
+        if now >= segment.end() {
+            // The next trade starts a new segment and resets the wrapped rule.
+            self.current = None;
+            self.session_changed = true;
+            return true;
+        }
+
+        self.session_changed = false;
+        self.rule.should_trigger_on_time(now)
+    }
+
+    fn accepts(&mut self, trade: &T) -> bool {
+        !self.drop_outside_sessions
+            || matches!(self.segment_of(trade.timestamp()), Segment::Session { .. })
+    }
+}
+
+impl<R, Tz> TriggerReason for SessionRule<R, Tz>
+where
+    R: TriggerReason,
+{
+    /// The reason of the wrapped rule, or None if the candle was finished by a session boundary
+    type Reason = Option<R::Reason>;
+
+    fn trigger_reason(&self) -> Self::Reason {
+        if self.session_changed {
+            return None;
+        }
+        Some(self.rule.trigger_reason())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+    use chrono_tz::America::{Chicago, New_York};
+
+    use super::*;
+    use crate::{
+        aggregate_all_trades,
+        candle_components::{CloseTimeStamp, NumTrades, OpenTimeStamp},
+        Aggregator, AlignedTimeRule, Candle, GenericAggregator, TickRule, Trade, M30,
+    };
+
+    use Weekday::*;
+
+    #[derive(Default, Debug, Clone, Candle)]
+    struct SessionCandle {
+        open_timestamp: OpenTimeStamp<i64>,
+        close_timestamp: CloseTimeStamp<i64>,
+        num_trades: NumTrades<u32>,
+    }
+
+    const WEEKDAYS: [Weekday; 5] = [Mon, Tue, Wed, Thu, Fri];
+
+    fn hms(h: u32, m: u32) -> NaiveTime {
+        NaiveTime::from_hms_opt(h, m, 0).unwrap()
+    }
+
+    fn utc(y: i32, mo: u32, d: u32, h: u32, mi: u32) -> i64 {
+        Utc.with_ymd_and_hms(y, mo, d, h, mi, 0)
+            .unwrap()
+            .timestamp_millis()
+    }
+
+    fn trade(timestamp: i64) -> Trade {
+        Trade {
+            timestamp,
+            price: 100.0,
+            size: 1.0,
+        }
+    }
+
+    fn equity_schedule() -> SessionSchedule<chrono_tz::Tz> {
+        SessionSchedule::new(
+            New_York,
+            vec![
+                Session::new("pre-market", hms(4, 0), hms(9, 30), &WEEKDAYS).unwrap(),
+                Session::new("regular", hms(9, 30), hms(16, 0), &WEEKDAYS).unwrap(),
+            ],
+        )
+        .unwrap()
+    }
+
+    fn equity_rule(drop_outside_sessions: bool) -> SessionRule<AlignedTimeRule, chrono_tz::Tz> {
+        SessionRule::new(
+            AlignedTimeRule::new(M30, TimestampResolution::Millisecond),
+            equity_schedule(),
+            drop_outside_sessions,
+            TimestampResolution::Millisecond,
+        )
+    }
+
+    /// A trade every 10 minutes from Monday 03:00 until Tuesday 10:00 New York time,
+    /// which is 5 hours behind UTC at that time.
+    fn equity_trades() -> Vec<Trade> {
+        (utc(2024, 3, 4, 8, 0)..utc(2024, 3, 5, 15, 0))
+            .step_by(600_000)
+            .map(trade)
+            .collect()
+    }
+
+    #[test]
+    fn session_invalid_params() {
+        assert!(Session::new("", hms(9, 30), hms(9, 30), &WEEKDAYS).is_err());
+        assert!(Session::new("", hms(9, 30), hms(16, 0), &[]).is_err());
+        assert!(SessionSchedule::new(New_York, vec![]).is_err());
+    }
+
+    #[test]
+    fn session_rule_drop_outside_sessions() {
+        let mut aggregator =
+            GenericAggregator::<SessionCandle, _, Trade>::new(equity_rule(true), false);
+        let mut candles = vec![];
+        let mut session_closes = 0;
+        for t in equity_trades().iter() {
+            if let Some(e) = aggregator.update_with_event(t) {
+                candles.push(e.candle);
+                session_closes += e.reason.is_none() as usize;
+            }
+        }
+        // 11 pre-market and 13 regular candles on Monday, 11 pre-market candles on Tuesday
+        assert_eq!(candles.len(), 35);
+        assert_eq!(session_closes, 3);
+        assert!(candles.iter().all(|c| c.num_trades() == 3));
+
+        assert_eq!(candles[0].open_timestamp(), utc(2024, 3, 4, 9, 0));
+        // The last pre-market candle is 30 minutes long and closes right before the regular session
+        assert_eq!(candles[10].open_timestamp(), utc(2024, 3, 4, 14, 0));
+        assert_eq!(candles[10].close_timestamp(), utc(2024, 3, 4, 14, 20));
+        assert_eq!(candles[11].open_timestamp(), utc(2024, 3, 4, 14, 30));
+        assert_eq!(candles[23].close_timestamp(), utc(2024, 3, 4, 20, 50));
+        // Nothing between the close and the next pre-market open is included
+        assert_eq!(candles[24].open_timestamp(), utc(2024, 3, 5, 9, 0));
+
+        let rule = aggregator.aggregation_rule();
+        assert_eq!(rule.current_session().unwrap().name(), "regular");
+        assert_eq!(aggregator.unfinished_candle().num_trades(), 3);
+    }
+
+    #[test]
+    fn session_rule_keep_outside_sessions() {
+        let mut aggregator =
+            GenericAggregator::<SessionCandle, _, Trade>::new(equity_rule(false), false);
+        let candles = aggregate_all_trades(&equity_trades(), &mut aggregator);
+        // Additionally 2 candles before the pre-market on Monday and 24 overnight
+        assert_eq!(candles.len(), 61);
+        assert_eq!(candles[0].open_timestamp(), utc(2024, 3, 4, 8, 0));
+        assert_eq!(candles[1].close_timestamp(), utc(2024, 3, 4, 8, 50));
+        assert_eq!(candles[2].open_timestamp(), utc(2024, 3, 4, 9, 0));
+        // The first overnight candle starts with the close of the regular session
+        assert_eq!(candles[26].open_timestamp(), utc(2024, 3, 4, 21, 0));
+        assert_eq!(candles[49].close_timestamp(), utc(2024, 3, 5, 8, 50));
+        assert!(aggregator.aggregation_rule().current_session().is_some());
+    }
+
+    #[test]
+    fn session_rule_overnight_session_on_time() {
+        // Futures trade from 17:00 Chicago time until 16:00 the next day, opening Sunday through Thursday
+        let schedule = SessionSchedule::new(
+            Chicago,
+            vec![
+                Session::new("globex", hms(17, 0), hms(16, 0), &[Sun, Mon, Tue, Wed, Thu]).unwrap(),
+            ],
+        )
+        .unwrap();
+        let rule = SessionRule::new(
+            TickRule::new(1000),
+            schedule,
+            true,
+            TimestampResolution::Millisecond,
+        );
+        let mut aggregator = GenericAggregator::<SessionCandle, _, Trade>::new(rule, false);
+
+        // 2024-07-05 is a Friday, Chicago is 5 hours behind UTC
+        assert!(aggregator.update(&trade(utc(2024, 7, 5, 20, 0))).is_none());
+        assert!(aggregator.update(&trade(utc(2024, 7, 5, 20, 30))).is_none());
+        assert!(aggregator.on_time(utc(2024, 7, 5, 20, 59)).is_none());
+        let candle = aggregator.on_time(utc(2024, 7, 5, 21, 0)).unwrap();
+        assert_eq!(candle.num_trades(), 2);
+
+        // Dropped as the market is closed over the weekend
+        assert!(aggregator.update(&trade(utc(2024, 7, 6, 12, 0))).is_none());
+        assert!(aggregator.update(&trade(utc(2024, 7, 7, 21, 59))).is_none());
+        assert_eq!(aggregator.unfinished_candle().num_trades(), 0);
+
+        // The session opening on Sunday evening
+        assert!(aggregator.update(&trade(utc(2024, 7, 7, 22, 0))).is_none());
+        assert!(aggregator.update(&trade(utc(2024, 7, 8, 20, 59))).is_none());
+        let candle = aggregator.update(&trade(utc(2024, 7, 8, 22, 0))).unwrap();
+        assert_eq!(candle.open_timestamp(), utc(2024, 7, 7, 22, 0));
+        assert_eq!(candle.num_trades(), 2);
+    }
+}
@@ -0,0 +1,128 @@
+use super::imbalance::ImbalanceState;
+use crate::{AggregationRule, Error, ModularCandle, Result, TakerTrade, TriggerReason};
+
+/// Creates tick imbalance bars as defined by Marcos Lopez de Prado in "Advances in Financial Machine Learning".
+/// Each trade is classified as buy (+1) or sell (-1) using the tick rule and the signs are accumulated.
+/// A candle is finished once the absolute cumulative tick imbalance reaches the expected threshold,
+/// which is the EWMA of past bar lengths multiplied by the absolute EWMA of the past per-bar mean imbalances:
+/// `|theta_T| >= E[T] * |2P[b = 1] - 1|`
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TickImbalanceRule {
+    state: ImbalanceState,
+}
+
+impl TickImbalanceRule {
+    /// Create a new instance of the `TickImbalanceRule`
+    ///
+    /// # Arguments:
+    /// `initial_expected_num_ticks`: The expected bar length used until the first bar is finished.
+    /// `initial_expected_imbalance`: The expected imbalance per tick used until the first bar is finished, in range (0, 1].
+    /// `ewma_span`: The number of past bars the expectations are smoothed over.
+    ///
+    pub fn new(
+        initial_expected_num_ticks: f64,
+        initial_expected_imbalance: f64,
+        ewma_span: usize,
+    ) -> Result<Self> {
+        if !initial_expected_num_ticks.is_finite()
+            || initial_expected_num_ticks <= 0.0
+            || initial_expected_imbalance.is_nan()
+            || initial_expected_imbalance <= 0.0
+            || initial_expected_imbalance > 1.0
+            || ewma_span == 0
+        {
+            return Err(Error::InvalidParam);
+        }
+        Ok(Self {
+            state: ImbalanceState::new(
+                initial_expected_num_ticks,
+                initial_expected_imbalance,
+                ewma_span,
+            ),
+        })
+    }
+
+    /// The absolute tick imbalance the current bar needs to reach before it is finished
+    pub fn expected_threshold(&self) -> f64 {
+        self.state.expected_threshold()
+    }
+}
+
+impl<C, T> AggregationRule<C, T> for TickImbalanceRule
+where
+    C: ModularCandle<T>,
+    T: TakerTrade,
+{
+    fn should_trigger(&mut self, trade: &T, _candle: &C) -> bool {
+        self.state.update(trade.price(), 1.0)
+    }
+
+    fn reset(&mut self, _trade: &T) {
+        self.state.reset();
+    }
+}
+
+impl TriggerReason for TickImbalanceRule {
+    /// The cumulative signed tick imbalance of the finished candle
+    type Reason = f64;
+
+    fn trigger_reason(&self) -> f64 {
+        self.state.last_imbalance()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        aggregate_all_trades, load_trades_from_csv, plot::OhlcCandle, GenericAggregator, Trade,
+    };
+
+    #[test]
+    fn tick_imbalance_rule_invalid_params() {
+        assert!(TickImbalanceRule::new(0.0, 0.5, 3).is_err());
+        assert!(TickImbalanceRule::new(3.0, 0.0, 3).is_err());
+        assert!(TickImbalanceRule::new(3.0, 1.5, 3).is_err());
+        assert!(TickImbalanceRule::new(3.0, 0.5, 0).is_err());
+        assert!(TickImbalanceRule::new(f64::NAN, 0.5, 3).is_err());
+        assert!(TickImbalanceRule::new(f64::INFINITY, 0.5, 3).is_err());
+        assert!(TickImbalanceRule::new(3.0, f64::NAN, 3).is_err());
+        assert!(TickImbalanceRule::new(3.0, 0.5, 3).is_ok());
+    }
+
+    #[test]
+    fn tick_imbalance_rule() {
+        let trades = load_trades_from_csv("data/Bitstamp_BTCEUR_1M.csv").unwrap();
+
+        // The tick signs of the trades are: [0, -1, 1, -1, -1, -1, -1, -1, 1, -1, 1]
+        let mut rule = TickImbalanceRule::new(3.0, 0.5, 3).unwrap();
+        let candle = OhlcCandle::default();
+        let triggers: Vec<bool> = trades
+            .iter()
+            .map(|t| rule.should_trigger(t, &candle))
+            .collect();
+        assert_eq!(
+            triggers,
+            vec![false, false, false, false, true, true, false, true, false, false, false]
+        );
+        // E[T] = 2.25, E[b] = -0.7375
+        assert_eq!(rule.expected_threshold(), 2.25 * 0.7375);
+    }
+
+    #[test]
+    fn tick_imbalance_rule_aggregation() {
+        let trades = load_trades_from_csv("data/Bitstamp_BTCEUR_1M.csv").unwrap();
+
+        let rule = TickImbalanceRule::new(3.0, 0.5, 3).unwrap();
+        let mut aggregator = GenericAggregator::<OhlcCandle, _, Trade>::new(rule, false);
+        let candles = aggregate_all_trades(&trades, &mut aggregator);
+        assert_eq!(candles.len(), 3);
+        assert_eq!(candles[0].open(), 27334.25);
+        assert_eq!(candles[0].close(), 27329.54);
+        assert_eq!(candles[1].open(), 27304.72);
+        assert_eq!(candles[1].close(), 27304.72);
+        assert_eq!(candles[2].open(), 27304.72);
+        assert_eq!(candles[2].close(), 27304.31);
+    }
+}
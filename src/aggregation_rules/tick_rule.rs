@@ -1,7 +1,8 @@
-use crate::{AggregationRule, ModularCandle, TakerTrade};
+use crate::{AggregationRule, ModularCandle, TakerTrade, TriggerReason};
 
 /// Creates candles every n ticks
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct TickRule {
     init: bool,
     tick_counter: usize,
@@ -42,6 +43,19 @@ where
         }
         false
     }
+
+    fn reset(&mut self, _trade: &T) {
+        self.init = true;
+    }
+}
+
+impl TriggerReason for TickRule {
+    /// The number of ticks counted
+    type Reason = usize;
+
+    fn trigger_reason(&self) -> usize {
+        self.tick_counter
+    }
 }
 
 #[cfg(test)]
@@ -0,0 +1,124 @@
+use super::run::RunState;
+use crate::{AggregationRule, Error, ModularCandle, Result, TakerTrade, TriggerReason};
+
+/// Creates tick run bars as defined by Marcos Lopez de Prado in "Advances in Financial Machine Learning".
+/// The side of each trade is taken from the sign of its size and the buys and sells are counted separately.
+/// A candle is finished once the larger count reaches the expected run,
+/// which is the EWMA of past bar lengths multiplied by the larger expected side probability:
+/// `max(#buys, #sells) >= E[T] * max(P[b = 1], 1 - P[b = 1])`
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TickRunRule {
+    state: RunState,
+}
+
+impl TickRunRule {
+    /// Create a new instance of the `TickRunRule`
+    ///
+    /// # Arguments:
+    /// `initial_expected_num_ticks`: The expected bar length used until the first bar is finished.
+    /// `initial_expected_buy_proportion`: The expected fraction of buys used until the first bar is finished, in range [0, 1].
+    /// `ewma_span`: The number of past bars the expectations are smoothed over.
+    ///
+    pub fn new(
+        initial_expected_num_ticks: f64,
+        initial_expected_buy_proportion: f64,
+        ewma_span: usize,
+    ) -> Result<Self> {
+        if !initial_expected_num_ticks.is_finite()
+            || initial_expected_num_ticks <= 0.0
+            || !(0.0..=1.0).contains(&initial_expected_buy_proportion)
+            || ewma_span == 0
+        {
+            return Err(Error::InvalidParam);
+        }
+        Ok(Self {
+            state: RunState::new(
+                initial_expected_num_ticks,
+                initial_expected_buy_proportion,
+                1.0,
+                ewma_span,
+            ),
+        })
+    }
+
+    /// The number of one-sided ticks the current bar needs to reach before it is finished
+    pub fn expected_threshold(&self) -> f64 {
+        self.state.expected_threshold()
+    }
+}
+
+impl<C, T> AggregationRule<C, T> for TickRunRule
+where
+    C: ModularCandle<T>,
+    T: TakerTrade,
+{
+    fn should_trigger(&mut self, trade: &T, _candle: &C) -> bool {
+        self.state.update(trade.size() > 0.0, 1.0)
+    }
+
+    fn reset(&mut self, _trade: &T) {
+        self.state.reset();
+    }
+}
+
+impl TriggerReason for TickRunRule {
+    /// The number of one-sided ticks of the finished candle
+    type Reason = f64;
+
+    fn trigger_reason(&self) -> f64 {
+        self.state.last_run()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use round::round;
+
+    use super::*;
+    use crate::{
+        aggregate_all_trades, candle_components::tests::TRADES, plot::OhlcCandle,
+        GenericAggregator, Trade,
+    };
+
+    #[test]
+    fn tick_run_rule_invalid_params() {
+        assert!(TickRunRule::new(0.0, 0.5, 3).is_err());
+        assert!(TickRunRule::new(4.0, -0.1, 3).is_err());
+        assert!(TickRunRule::new(4.0, 1.1, 3).is_err());
+        assert!(TickRunRule::new(4.0, 0.5, 0).is_err());
+        assert!(TickRunRule::new(f64::NAN, 0.5, 3).is_err());
+        assert!(TickRunRule::new(4.0, f64::NAN, 3).is_err());
+        assert!(TickRunRule::new(4.0, 0.5, 3).is_ok());
+    }
+
+    #[test]
+    fn tick_run_rule() {
+        let mut rule = TickRunRule::new(4.0, 0.5, 3).unwrap();
+        assert_eq!(rule.expected_threshold(), 2.0);
+
+        let candle = OhlcCandle::default();
+        let triggers: Vec<bool> = TRADES
+            .iter()
+            .map(|t| rule.should_trigger(t, &candle))
+            .collect();
+        assert_eq!(
+            triggers,
+            vec![false, false, true, false, false, false, false, true, false, false]
+        );
+        // E[T] = 4.25, P[b = 1] = 0.5917
+        assert_eq!(round(rule.expected_threshold(), 4), 2.5146);
+    }
+
+    #[test]
+    fn tick_run_rule_aggregation() {
+        let rule = TickRunRule::new(4.0, 0.5, 3).unwrap();
+        let mut aggregator = GenericAggregator::<OhlcCandle, _, Trade>::new(rule, false);
+        let candles = aggregate_all_trades(&TRADES, &mut aggregator);
+        assert_eq!(candles.len(), 2);
+        assert_eq!(candles[0].open(), 100.0);
+        assert_eq!(candles[0].close(), 101.0);
+        assert_eq!(candles[1].open(), 100.0);
+        assert_eq!(candles[1].close(), 102.0);
+    }
+}
@@ -0,0 +1,41 @@
+/// Classifies trades as buys (+1) or sells (-1) using the tick rule:
+/// An uptick is a buy, a downtick a sell and a zero tick keeps the previous sign.
+/// The very first trade has no reference price and is classified as 0.
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub(crate) struct TickSign {
+    last_price: Option<f64>,
+    sign: f64,
+}
+
+impl TickSign {
+    /// Classify the trade with the given price, returning its sign
+    pub(crate) fn update(&mut self, price: f64) -> f64 {
+        if let Some(last_price) = self.last_price {
+            if price > last_price {
+                self.sign = 1.0;
+            } else if price < last_price {
+                self.sign = -1.0;
+            }
+        }
+        self.last_price = Some(price);
+
+        self.sign
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tick_sign() {
+        let mut s = TickSign::default();
+        assert_eq!(s.update(100.0), 0.0);
+        assert_eq!(s.update(101.0), 1.0);
+        assert_eq!(s.update(101.0), 1.0);
+        assert_eq!(s.update(100.5), -1.0);
+        assert_eq!(s.update(100.5), -1.0);
+        assert_eq!(s.update(102.0), 1.0);
+    }
+}
@@ -1,8 +1,12 @@
-use crate::{AggregationRule, MillisecondPeriod, ModularCandle, TakerTrade, TimestampResolution};
+use crate::{
+    AggregationRule, GapFill, MillisecondPeriod, ModularCandle, Result, TakerTrade,
+    TimestampResolution, TriggerReason,
+};
 
 /// The classic time based aggregation rule,
 /// creating a new candle every n seconds
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct TimeRule {
     // The timestamp this rule uses as a reference
     // in the unit of the incoming trades.
@@ -10,6 +14,10 @@ pub struct TimeRule {
 
     // The period for the candle in the timestamp resolution of the candle as provided in the constructor.
     period_in_units_from_trade: i64,
+
+    // The reference timestamp of the candle finished by `should_trigger_on_time`, until the next trade,
+    // so `GapFill::undo_on_time` can fill the periods after that candle.
+    on_time_reference: Option<i64>,
 }
 
 impl TimeRule {
@@ -20,22 +28,28 @@ impl TimeRule {
     /// `period_ms`: How many milliseconds a candle will contain.
     /// `trade_timestamp_resolution`: The resolution each Trade timestamp will have
     ///
+    /// # Panics:
+    /// If the period is not a whole number of seconds for `TimestampResolution::Second`,
+    /// use `try_new` to handle such a period instead.
     pub fn new(
         period_ms: MillisecondPeriod,
         trade_timestamp_resolution: TimestampResolution,
     ) -> Self {
-        use TimestampResolution::*;
-        // Given the timestamp resolution of the trades, a certain multiplier is required to compute the number of units for the sample period.
-        let ts_multiplier = match trade_timestamp_resolution {
-            Millisecond => 1,
-            Microsecond => 1_000,
-            Nanosecond => 1_000_000,
-        };
-
-        Self {
+        Self::try_new(period_ms, trade_timestamp_resolution)
+            .expect("The period must be a whole number of seconds for second timestamps")
+    }
+
+    /// Create a new instance like `new`,
+    /// but return an error if the period is not a whole number of seconds for `TimestampResolution::Second`.
+    pub fn try_new(
+        period_ms: MillisecondPeriod,
+        trade_timestamp_resolution: TimestampResolution,
+    ) -> Result<Self> {
+        Ok(Self {
             reference_timestamp: 0,
-            period_in_units_from_trade: period_ms.get() as i64 * ts_multiplier,
-        }
+            period_in_units_from_trade: trade_timestamp_resolution.period_in_units(period_ms)?,
+            on_time_reference: None,
+        })
     }
 }
 
@@ -45,6 +59,7 @@ where
     T: TakerTrade,
 {
     fn should_trigger(&mut self, trade: &T, _candle: &C) -> bool {
+        self.on_time_reference = None;
         if self.reference_timestamp == 0 {
             self.reference_timestamp = trade.timestamp();
         }
@@ -55,20 +70,70 @@ where
             // If the period is too small, then the trade timestamps will out-pace and always trigger.
             // Alternatively doing `self.reference_timestamp = trade.timestamp()` will cause drift
             // and not produce enough samples.
-            self.reference_timestamp = self.reference_timestamp + self.period_in_units_from_trade;
+            self.reference_timestamp += self.period_in_units_from_trade;
+        }
+
+        should_trigger
+    }
+
+    fn reset(&mut self, trade: &T) {
+        self.reference_timestamp = trade.timestamp();
+    }
+
+    fn should_trigger_on_time(&mut self, now: i64) -> bool {
+        if self.reference_timestamp == 0 {
+            // No trade has been observed yet
+            return false;
+        }
+        let reference_timestamp = self.reference_timestamp;
+        let mut should_trigger = false;
+        // Advance to the period containing `now`, as there is no point in triggering once for each passed period.
+        while now - self.reference_timestamp > self.period_in_units_from_trade {
+            self.reference_timestamp += self.period_in_units_from_trade;
+            should_trigger = true;
+        }
+        if should_trigger {
+            self.on_time_reference.get_or_insert(reference_timestamp);
         }
 
         should_trigger
     }
 }
 
+impl GapFill for TimeRule {
+    fn next_empty_period(&mut self, timestamp: i64) -> Option<(i64, i64)> {
+        if timestamp - self.reference_timestamp <= self.period_in_units_from_trade {
+            return None;
+        }
+        let open = self.reference_timestamp;
+        self.reference_timestamp += self.period_in_units_from_trade;
+
+        Some((open, self.reference_timestamp - 1))
+    }
+
+    fn undo_on_time(&mut self) {
+        if let Some(reference_timestamp) = self.on_time_reference.take() {
+            self.reference_timestamp = reference_timestamp;
+        }
+    }
+}
+
+impl TriggerReason for TimeRule {
+    /// The start of the new period, in the unit of the incoming trades
+    type Reason = i64;
+
+    fn trigger_reason(&self) -> i64 {
+        self.reference_timestamp
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::{
         aggregate_all_trades, load_trades_from_csv,
         plot::{plot_ohlc_candles, OhlcCandle},
-        GenericAggregator, Trade, H1, M15, M5,
+        AlignedTimeRule, GenericAggregator, Trade, H1, M15, M5,
     };
 
     #[test]
@@ -156,4 +221,78 @@ mod tests {
         let candles = aggregate_all_trades(&trades_ns, &mut aggregator);
         assert_eq!(candles.len(), 396);
     }
+
+    #[test]
+    fn time_rule_on_time() {
+        use crate::{candle_components::tests::TRADES, MillisecondPeriod};
+
+        let mut rule = TimeRule::new(
+            MillisecondPeriod::from_non_zero_secs(10),
+            TimestampResolution::Millisecond,
+        );
+        let candle = OhlcCandle::default();
+        let t0 = TRADES[0].timestamp;
+        assert!(!AggregationRule::<OhlcCandle, Trade>::should_trigger_on_time(&mut rule, t0));
+
+        assert!(!rule.should_trigger(&TRADES[0], &candle));
+        assert!(
+            !AggregationRule::<OhlcCandle, Trade>::should_trigger_on_time(&mut rule, t0 + 10_000)
+        );
+        assert!(
+            AggregationRule::<OhlcCandle, Trade>::should_trigger_on_time(&mut rule, t0 + 35_000)
+        );
+        // Advanced to the period containing the given time
+        assert_eq!(rule.trigger_reason(), t0 + 30_000);
+        assert!(!rule.should_trigger(&TRADES[3], &candle));
+        assert!(rule.should_trigger(&TRADES[5], &candle));
+    }
+
+    #[test]
+    fn time_rule_second_resolution() {
+        use crate::{candle_components::tests::TRADES, MillisecondPeriod};
+
+        let trades_s: Vec<Trade> = TRADES
+            .iter()
+            .map(|t| Trade {
+                timestamp: t.timestamp / 1_000,
+                ..*t
+            })
+            .collect();
+
+        let period = MillisecondPeriod::from_non_zero_secs(25);
+        let mut aggregator = GenericAggregator::<OhlcCandle, TimeRule, Trade>::new(
+            TimeRule::new(period, TimestampResolution::Millisecond),
+            false,
+        );
+        let expected = aggregate_all_trades(&TRADES, &mut aggregator);
+        let mut aggregator = GenericAggregator::<OhlcCandle, TimeRule, Trade>::new(
+            TimeRule::new(period, TimestampResolution::Second),
+            false,
+        );
+        let candles = aggregate_all_trades(&trades_s, &mut aggregator);
+        assert_eq!(candles.len(), 3);
+        assert_eq!(candles.len(), expected.len());
+        for (c, e) in candles.iter().zip(expected.iter()) {
+            assert_eq!(c.open(), e.open());
+            assert_eq!(c.close(), e.close());
+        }
+    }
+
+    #[test]
+    fn time_rule_fractional_seconds() {
+        // 1.5 seconds can't be expressed in second timestamps
+        let period = MillisecondPeriod::from_non_zero(1_500);
+        assert!(TimeRule::try_new(period, TimestampResolution::Second).is_err());
+        assert!(AlignedTimeRule::try_new(period, TimestampResolution::Second).is_err());
+        assert!(TimeRule::try_new(period, TimestampResolution::Millisecond).is_ok());
+    }
+
+    #[test]
+    #[should_panic]
+    fn time_rule_fractional_seconds_panic() {
+        TimeRule::new(
+            MillisecondPeriod::from_non_zero(1_500),
+            TimestampResolution::Second,
+        );
+    }
 }
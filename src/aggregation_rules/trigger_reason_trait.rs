@@ -0,0 +1,11 @@
+/// Exposes why an `AggregationRule` triggered,
+/// allowing candle boundaries to be audited and reproduced.
+/// See `GenericAggregator::update_with_event`.
+pub trait TriggerReason {
+    /// The rule specific reason
+    type Reason: std::fmt::Debug + Clone;
+
+    /// The reason of the most recent trigger.
+    /// Only meaningful directly after `should_trigger` returned true.
+    fn trigger_reason(&self) -> Self::Reason;
+}
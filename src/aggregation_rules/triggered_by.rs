@@ -0,0 +1,36 @@
+/// Reports which child of a combining rule, such as `AnyRule` or `AllRule`,
+/// caused the most recent candle to be finished
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TriggeredBy {
+    /// Only the first rule triggered
+    First,
+    /// Only the second rule triggered
+    Second,
+    /// Both rules triggered on the same trade
+    Both,
+}
+
+impl TriggeredBy {
+    /// Combine the trigger flags of two rules, returning None if neither triggered
+    pub(crate) fn from_flags(first: bool, second: bool) -> Option<Self> {
+        match (first, second) {
+            (true, true) => Some(Self::Both),
+            (true, false) => Some(Self::First),
+            (false, true) => Some(Self::Second),
+            (false, false) => None,
+        }
+    }
+}
+
+/// The trigger reason of a combining rule, such as `AnyRule` or `AllRule`
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CombinedReason<A, B> {
+    /// Which rule triggered
+    pub triggered_by: Option<TriggeredBy>,
+    /// The most recent reason of the first rule, only relevant if it triggered
+    pub first: A,
+    /// The most recent reason of the second rule, only relevant if it triggered
+    pub second: B,
+}
@@ -0,0 +1,115 @@
+use super::imbalance::ImbalanceState;
+use crate::{AggregationRule, By, Error, ModularCandle, Result, TakerTrade, TriggerReason};
+
+/// Creates volume imbalance bars as defined by Marcos Lopez de Prado in "Advances in Financial Machine Learning".
+/// Each trade is classified as buy or sell using the tick rule and the signed volume is accumulated.
+/// A candle is finished once the absolute cumulative volume imbalance reaches the expected threshold,
+/// which is the EWMA of past bar lengths multiplied by the absolute EWMA of the past per-tick volume imbalances:
+/// `|theta_T| >= E[T] * |2v+ - E[v]|`
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct VolumeImbalanceRule {
+    /// See docs on By enum for details
+    by: By,
+
+    state: ImbalanceState,
+}
+
+impl VolumeImbalanceRule {
+    /// Create a new instance of the `VolumeImbalanceRule`
+    ///
+    /// # Arguments:
+    /// `initial_expected_num_ticks`: The expected bar length used until the first bar is finished.
+    /// `initial_expected_imbalance`: The expected volume imbalance per tick used until the first bar is finished.
+    /// `ewma_span`: The number of past bars the expectations are smoothed over.
+    /// `by`: Whether the volume is measured in the base or quote currency or as notional value, see docs on `By`.
+    ///
+    pub fn new(
+        initial_expected_num_ticks: f64,
+        initial_expected_imbalance: f64,
+        ewma_span: usize,
+        by: By,
+    ) -> Result<Self> {
+        if !initial_expected_num_ticks.is_finite()
+            || initial_expected_num_ticks <= 0.0
+            || !initial_expected_imbalance.is_finite()
+            || initial_expected_imbalance <= 0.0
+            || ewma_span == 0
+        {
+            return Err(Error::InvalidParam);
+        }
+        Ok(Self {
+            by,
+            state: ImbalanceState::new(
+                initial_expected_num_ticks,
+                initial_expected_imbalance,
+                ewma_span,
+            ),
+        })
+    }
+
+    /// The absolute volume imbalance the current bar needs to reach before it is finished
+    pub fn expected_threshold(&self) -> f64 {
+        self.state.expected_threshold()
+    }
+}
+
+impl<C, T> AggregationRule<C, T> for VolumeImbalanceRule
+where
+    C: ModularCandle<T>,
+    T: TakerTrade,
+{
+    fn should_trigger(&mut self, trade: &T, _candle: &C) -> bool {
+        let volume = self.by.volume(trade, trade.size());
+        self.state.update(trade.price(), volume)
+    }
+
+    fn reset(&mut self, _trade: &T) {
+        self.state.reset();
+    }
+}
+
+impl TriggerReason for VolumeImbalanceRule {
+    /// The cumulative signed volume imbalance of the finished candle
+    type Reason = f64;
+
+    fn trigger_reason(&self) -> f64 {
+        self.state.last_imbalance()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use round::round;
+
+    use super::*;
+    use crate::{load_trades_from_csv, plot::OhlcCandle};
+
+    #[test]
+    fn volume_imbalance_rule_invalid_params() {
+        assert!(VolumeImbalanceRule::new(0.0, 0.01, 3, By::Quote).is_err());
+        assert!(VolumeImbalanceRule::new(3.0, 0.0, 3, By::Quote).is_err());
+        assert!(VolumeImbalanceRule::new(3.0, 0.01, 0, By::Quote).is_err());
+        assert!(VolumeImbalanceRule::new(f64::NAN, 0.01, 3, By::Quote).is_err());
+        assert!(VolumeImbalanceRule::new(3.0, f64::NAN, 3, By::Quote).is_err());
+        assert!(VolumeImbalanceRule::new(3.0, 0.01, 3, By::Quote).is_ok());
+    }
+
+    #[test]
+    fn volume_imbalance_rule() {
+        let trades = load_trades_from_csv("data/Bitstamp_BTCEUR_1M.csv").unwrap();
+
+        let mut rule = VolumeImbalanceRule::new(3.0, 0.01, 3, By::Quote).unwrap();
+        assert_eq!(rule.expected_threshold(), 0.03);
+        let candle = OhlcCandle::default();
+        let triggers: Vec<bool> = trades
+            .iter()
+            .map(|t| rule.should_trigger(t, &candle))
+            .collect();
+        assert_eq!(
+            triggers,
+            vec![false, false, false, false, true, true, false, false, false, false, false]
+        );
+        assert_eq!(round(rule.expected_threshold(), 4), 0.253);
+    }
+}
@@ -1,9 +1,16 @@
-use crate::{AggregationRule, By, Error, ModularCandle, Result, TakerTrade};
+use crate::{
+    AggregationRule, By, Error, ModularCandle, Result, TakerTrade, TriggerReason, VolumeSplit,
+    SPLIT_EPSILON,
+};
 
 /// Creates candles every n units of volume traded.
+/// With `By::Notional`, these are dollar bars, following the `ContractSpec` of the trades.
 /// If the last trade needed to complete a bucket is for a size greater than required,
-/// the excess size is given to the next bucket
+/// the excess size is given to the next bucket.
+/// Use `GenericAggregator::update_exact_buckets` to split such a trade instead,
+/// so that every candle contains exactly the threshold volume.
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct VolumeRule {
     /// See docs on By enum for details
     by: By,
@@ -18,7 +25,7 @@ pub struct VolumeRule {
 impl VolumeRule {
     /// Create a new instance with the given volume threshold
     pub fn new(threshold_vol: f64, by: By) -> Result<Self> {
-        if threshold_vol <= 0.0 {
+        if !threshold_vol.is_finite() || threshold_vol <= 0.0 {
             return Err(Error::InvalidParam);
         }
         Ok(Self {
@@ -38,14 +45,214 @@ where
         if self.cum_vol >= self.threshold_vol {
             // If the last trade needed to complete a bucket is for a size greater than required,
             // the excess size is given to the next bucket
-            self.cum_vol = self.cum_vol - self.threshold_vol;
+            self.cum_vol -= self.threshold_vol;
             debug_assert!(self.cum_vol >= 0.0);
         }
-        self.cum_vol += match self.by {
-            By::Quote => trade.size().abs(),
-            By::Base => trade.size().abs() / trade.price(),
-        };
+        self.cum_vol += self.by.volume(trade, trade.size());
 
         self.cum_vol >= self.threshold_vol
     }
+
+    fn reset(&mut self, trade: &T) {
+        // The candle was finished by another rule, so there is no excess volume to carry over,
+        // but the trade is part of the new candle.
+        self.cum_vol = self.by.volume(trade, trade.size());
+    }
+}
+
+impl<T: TakerTrade> VolumeSplit<T> for VolumeRule {
+    fn fill_bucket(&mut self, trade: &T, size: f64) -> Option<f64> {
+        if self.cum_vol >= self.threshold_vol {
+            // The bucket has already been completed by `should_trigger`,
+            // possibly multiple times over by a large trade added to a single candle
+            self.cum_vol %= self.threshold_vol;
+        }
+        // The volume is proportional to the size
+        let unit_volume = self.by.volume(trade, 1.0);
+        if !unit_volume.is_finite() || unit_volume == 0.0 {
+            // The trade can't be split, e.g. a trade at a price of zero when measured `By::Base`
+            return None;
+        }
+        let volume = unit_volume * size;
+        if self.cum_vol + volume < self.threshold_vol * (1.0 - SPLIT_EPSILON) {
+            self.cum_vol += volume;
+            return None;
+        }
+
+        let missing = self.threshold_vol - self.cum_vol;
+        self.cum_vol = 0.0;
+        Some((missing / unit_volume).min(size))
+    }
+}
+
+impl TriggerReason for VolumeRule {
+    /// The cumulative volume, including the excess that is carried over to the next bucket
+    type Reason = f64;
+
+    fn trigger_reason(&self) -> f64 {
+        self.cum_vol
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        candle_components::tests::{InverseCandle, InverseTrade, TRADES},
+        plot::OhlcCandle,
+        Aggregator, GenericAggregator, Trade,
+    };
+
+    #[test]
+    fn volume_rule_invalid_params() {
+        assert!(VolumeRule::new(0.0, By::Quote).is_err());
+        assert!(VolumeRule::new(f64::NAN, By::Notional).is_err());
+        assert!(VolumeRule::new(f64::INFINITY, By::Base).is_err());
+        assert!(VolumeRule::new(10.0, By::Notional).is_ok());
+    }
+
+    #[test]
+    fn volume_rule_reset_keeps_trade_volume() {
+        // The trade a sibling rule triggered on is part of the new candle, so it starts the next bucket
+        let mut rule = VolumeRule::new(30.0, By::Quote).unwrap();
+        let candle = OhlcCandle::default();
+        assert!(!rule.should_trigger(&TRADES[0], &candle));
+        AggregationRule::<OhlcCandle, _>::reset(&mut rule, &TRADES[1]);
+        assert_eq!(rule.trigger_reason(), 10.0);
+        assert!(rule.should_trigger(&TRADES[2], &candle));
+        assert_eq!(rule.trigger_reason(), 30.0);
+
+        // After a flush, the trade is only counted once when it is split into buckets
+        let rule = VolumeRule::new(30.0, By::Quote).unwrap();
+        let mut aggregator = GenericAggregator::<OhlcCandle, _, Trade>::new(rule, false);
+        assert!(aggregator.update_exact_buckets(&TRADES[0]).is_empty());
+        assert!(aggregator.flush().is_some());
+        assert!(aggregator.update_exact_buckets(&TRADES[2]).is_empty());
+        assert_eq!(aggregator.update_exact_buckets(&TRADES[3]).len(), 1);
+    }
+
+    #[test]
+    fn volume_rule_exact_buckets_after_large_trade() {
+        use trade_aggregation_derive::Candle;
+
+        use crate::candle_components::{Volume, VolumeSells};
+
+        #[derive(Debug, Default, Clone, Candle)]
+        struct VolumeCandle {
+            volume: Volume,
+            volume_sells: VolumeSells,
+        }
+
+        let trade = |size: f64| Trade {
+            timestamp: 1_684_677_200_000,
+            price: 1.0,
+            size,
+        };
+
+        // A trade of more than twice the threshold, added to a single candle by `update`,
+        // completes its buckets without leaving a negative part for the next split trade
+        let rule = VolumeRule::new(5.0, By::Quote).unwrap();
+        let mut a = GenericAggregator::<VolumeCandle, _, Trade>::new(rule, false);
+        a.update(&trade(12.0));
+        let candles = a.update_exact_buckets(&trade(4.0));
+        assert_eq!(candles.len(), 1);
+        assert_eq!(candles[0].volume(), 15.0);
+        assert_eq!(candles[0].volume_sells(), 0.0);
+        assert_eq!(a.unfinished_candle().volume(), 1.0);
+    }
+
+    #[test]
+    fn volume_rule_notional_inverse_contract() {
+        // 20 contracts of 100 USD each at a price of 25_000 USD are worth 0.08 BTC
+        let trade = InverseTrade(Trade {
+            timestamp: 0,
+            price: 25_000.0,
+            size: -20.0,
+        });
+        let rule = VolumeRule::new(0.1, By::Notional).unwrap();
+        let mut aggregator = GenericAggregator::<InverseCandle, _, InverseTrade>::new(rule, false);
+        let triggers: Vec<Option<f64>> = (0..4)
+            .map(|_| aggregator.update(&trade).map(|c| c.notional()))
+            .collect();
+        // The excess is carried over, so each of the following trades completes a bucket
+        assert_eq!(triggers, vec![None, Some(0.08), Some(0.08), Some(0.08)]);
+
+        // 5 contracts complete the bucket of 0.1 BTC
+        let mut rule = VolumeRule::new(0.1, By::Notional).unwrap();
+        assert_eq!(rule.fill_bucket(&trade, 20.0), None);
+        let size = rule.fill_bucket(&trade, 20.0).unwrap();
+        assert!((size - 5.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn volume_rule_exact_buckets_zero_price() {
+        // A trade at a price of zero has an infinite base volume, so it can't be split into buckets
+        let trade = Trade {
+            timestamp: 0,
+            price: 0.0,
+            size: 10.0,
+        };
+        let rule = VolumeRule::new(5.0, By::Base).unwrap();
+        let mut a = GenericAggregator::<OhlcCandle, _, Trade>::new(rule, false);
+        assert!(a.update_exact_buckets(&trade).is_empty());
+        assert_eq!(a.unfinished_candle().close(), 0.0);
+
+        // The bucket is still completed by the following trades
+        let trade = Trade {
+            timestamp: 1,
+            price: 2.0,
+            size: 12.0,
+        };
+        assert_eq!(a.update_exact_buckets(&trade).len(), 1);
+
+        // The notional value of an inverse contract at a price of zero is infinite as well
+        let trade = InverseTrade(Trade {
+            timestamp: 0,
+            price: 0.0,
+            size: 20.0,
+        });
+        let rule = VolumeRule::new(0.1, By::Notional).unwrap();
+        let mut a = GenericAggregator::<InverseCandle, _, InverseTrade>::new(rule, false);
+        assert!(a.update_exact_buckets(&trade).is_empty());
+    }
+
+    #[test]
+    fn volume_rule_notional() {
+        let mut rule = VolumeRule::new(3_000.0, By::Notional).unwrap();
+        let candle = OhlcCandle::default();
+        let triggers: Vec<usize> = TRADES
+            .iter()
+            .enumerate()
+            .filter(|(_, t)| rule.should_trigger(*t, &candle))
+            .map(|(i, _)| i)
+            .collect();
+        // The excess notional of each bucket is carried over to the next one
+        assert_eq!(triggers, vec![2, 4, 6, 8]);
+    }
+
+    #[test]
+    fn volume_rule_notional_exact_buckets() {
+        use trade_aggregation_derive::Candle;
+
+        use crate::candle_components::Notional;
+
+        #[derive(Debug, Default, Clone, Candle)]
+        struct NotionalCandle {
+            notional: Notional,
+        }
+
+        let rule = VolumeRule::new(3_000.0, By::Notional).unwrap();
+        let mut a = GenericAggregator::<NotionalCandle, _, Trade>::new(rule, false);
+        let candles: Vec<_> = TRADES
+            .iter()
+            .flat_map(|t| a.update_exact_buckets(t))
+            .collect();
+
+        // The total notional of the trades is 14_280
+        assert_eq!(candles.len(), 4);
+        for c in candles.iter() {
+            assert!((c.notional() - 3_000.0).abs() < 1e-9);
+        }
+        assert!((a.unfinished_candle().notional() - 2_280.0).abs() < 1e-9);
+    }
 }
@@ -0,0 +1,119 @@
+use super::run::RunState;
+use crate::{AggregationRule, By, Error, ModularCandle, Result, TakerTrade, TriggerReason};
+
+/// Creates volume run bars as defined by Marcos Lopez de Prado in "Advances in Financial Machine Learning".
+/// The side of each trade is taken from the sign of its size and the buy and sell volumes are accumulated separately.
+/// A candle is finished once the larger volume reaches the expected run,
+/// which is the EWMA of past bar lengths multiplied by the larger expected one-sided volume per tick:
+/// `max(v_buy, v_sell) >= E[T] * max(P[b = 1] * E[v | b = 1], (1 - P[b = 1]) * E[v | b = -1])`
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct VolumeRunRule {
+    /// See docs on By enum for details
+    by: By,
+
+    state: RunState,
+}
+
+impl VolumeRunRule {
+    /// Create a new instance of the `VolumeRunRule`
+    ///
+    /// # Arguments:
+    /// `initial_expected_num_ticks`: The expected bar length used until the first bar is finished.
+    /// `initial_expected_buy_proportion`: The expected fraction of buys used until the first bar is finished, in range [0, 1].
+    /// `initial_expected_volume`: The expected volume of a single trade used until the first bar is finished.
+    /// `ewma_span`: The number of past bars the expectations are smoothed over.
+    /// `by`: Whether the volume is measured in the base or quote currency or as notional value, see docs on `By`.
+    ///
+    pub fn new(
+        initial_expected_num_ticks: f64,
+        initial_expected_buy_proportion: f64,
+        initial_expected_volume: f64,
+        ewma_span: usize,
+        by: By,
+    ) -> Result<Self> {
+        if !initial_expected_num_ticks.is_finite()
+            || initial_expected_num_ticks <= 0.0
+            || !(0.0..=1.0).contains(&initial_expected_buy_proportion)
+            || !initial_expected_volume.is_finite()
+            || initial_expected_volume <= 0.0
+            || ewma_span == 0
+        {
+            return Err(Error::InvalidParam);
+        }
+        Ok(Self {
+            by,
+            state: RunState::new(
+                initial_expected_num_ticks,
+                initial_expected_buy_proportion,
+                initial_expected_volume,
+                ewma_span,
+            ),
+        })
+    }
+
+    /// The one-sided volume the current bar needs to reach before it is finished
+    pub fn expected_threshold(&self) -> f64 {
+        self.state.expected_threshold()
+    }
+}
+
+impl<C, T> AggregationRule<C, T> for VolumeRunRule
+where
+    C: ModularCandle<T>,
+    T: TakerTrade,
+{
+    fn should_trigger(&mut self, trade: &T, _candle: &C) -> bool {
+        let volume = self.by.volume(trade, trade.size());
+        self.state.update(trade.size() > 0.0, volume)
+    }
+
+    fn reset(&mut self, _trade: &T) {
+        self.state.reset();
+    }
+}
+
+impl TriggerReason for VolumeRunRule {
+    /// The one-sided volume of the finished candle
+    type Reason = f64;
+
+    fn trigger_reason(&self) -> f64 {
+        self.state.last_run()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use round::round;
+
+    use super::*;
+    use crate::{candle_components::tests::TRADES, plot::OhlcCandle};
+
+    #[test]
+    fn volume_run_rule_invalid_params() {
+        assert!(VolumeRunRule::new(0.0, 0.5, 10.0, 3, By::Quote).is_err());
+        assert!(VolumeRunRule::new(4.0, 1.5, 10.0, 3, By::Quote).is_err());
+        assert!(VolumeRunRule::new(4.0, 0.5, 0.0, 3, By::Quote).is_err());
+        assert!(VolumeRunRule::new(4.0, 0.5, 10.0, 0, By::Quote).is_err());
+        assert!(VolumeRunRule::new(f64::NAN, 0.5, 10.0, 3, By::Quote).is_err());
+        assert!(VolumeRunRule::new(4.0, 0.5, f64::NAN, 3, By::Quote).is_err());
+        assert!(VolumeRunRule::new(4.0, 0.5, 10.0, 3, By::Quote).is_ok());
+    }
+
+    #[test]
+    fn volume_run_rule() {
+        let mut rule = VolumeRunRule::new(4.0, 0.5, 10.0, 3, By::Quote).unwrap();
+        assert_eq!(rule.expected_threshold(), 20.0);
+
+        let candle = OhlcCandle::default();
+        let triggers: Vec<bool> = TRADES
+            .iter()
+            .map(|t| rule.should_trigger(t, &candle))
+            .collect();
+        assert_eq!(
+            triggers,
+            vec![false, false, true, false, false, false, true, false, true, false]
+        );
+        assert_eq!(round(rule.expected_threshold(), 4), 34.6273);
+    }
+}
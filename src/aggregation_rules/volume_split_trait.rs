@@ -0,0 +1,22 @@
+use crate::TakerTrade;
+
+// Relative tolerance below which the floating point residue of splitting a trade is ignored,
+// e.g. a bucket of 0.9 is complete after 0.7 and 0.2, even though their sum is slightly less
+pub(crate) const SPLIT_EPSILON: f64 = 1e-9;
+
+/// Implemented by volume based rules, allowing an aggregator to split a trade
+/// that crosses the boundary of a bucket, so every candle contains exactly the volume threshold.
+/// See `GenericAggregator::update_exact_buckets`.
+pub trait VolumeSplit<T: TakerTrade> {
+    /// Adds as much of the size of a trade to the current bucket as fits into it.
+    ///
+    /// # Arguments:
+    /// trade: The trade that is split
+    /// size: The absolute size of the trade that has not been assigned to a bucket yet
+    ///
+    /// # Returns:
+    /// The absolute size that completed the current bucket, which is then finished,
+    /// or None if the whole size fits into the current bucket without completing it.
+    /// A bucket within a relative tolerance of its threshold is considered complete.
+    fn fill_bucket(&mut self, trade: &T, size: f64) -> Option<f64>;
+}
@@ -1,6 +1,9 @@
 use std::marker::PhantomData;
 
-use crate::{AggregationRule, ModularCandle, TakerTrade};
+use crate::{
+    AggregationEvent, AggregationRule, GapFill, ModularCandle, SplittableTrade, SyntheticTrade,
+    TakerTrade, TriggerReason, VolumeSplit, SPLIT_EPSILON,
+};
 
 /// Defines the needed methods for any online `Aggregator`
 pub trait Aggregator<Candle, T: TakerTrade> {
@@ -18,12 +21,37 @@ pub trait Aggregator<Candle, T: TakerTrade> {
     /// Accessing a `Candle` using this method does not guarantee that the `AggregationRule` is respected.
     /// It is generally advised to call `update` instead and use the resulting `Candle` if its `Some`.
     fn unfinished_candle(&self) -> &Candle;
+
+    /// Finishes the current `Candle` regardless of the `AggregationRule` and resets the aggregation state,
+    /// e.g. to emit the last partial candle at the end of a batch of trades.
+    ///
+    /// # Returns:
+    /// Some output only when the current candle contains at least one trade,
+    /// otherwise it returns None
+    fn flush(&mut self) -> Option<Candle>;
+
+    /// Finishes the current `Candle` without a new trade, if the `AggregationRule` allows it,
+    /// e.g. once the period of a time based rule has passed.
+    /// Call this periodically with the current wall clock time to receive candles on time,
+    /// instead of waiting for the next trade, which may take a while in illiquid markets.
+    /// The default implementation never finishes a candle.
+    ///
+    /// # Arguments:
+    /// now: The current time, in the unit of the trade timestamps
+    ///
+    /// # Returns:
+    /// Some output only when the candle was finished and contains at least one trade,
+    /// otherwise it returns None
+    fn on_time(&mut self, _now: i64) -> Option<Candle> {
+        None
+    }
 }
 
 /// An `Aggregator` that is generic over
 /// the type of Candle being produced,
 /// as well as by which rule the candle is created
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct GenericAggregator<C, R, T> {
     candle: C,
     aggregation_rule: R,
@@ -32,6 +60,16 @@ pub struct GenericAggregator<C, R, T> {
     // Examples uses include ensuring the close and open price of the current and next candle are equal.
     // If that's desired, set the field to true during construction of `Self`.
     include_trade_that_triggered_rule: bool,
+    // The number of trades this aggregator has been updated with.
+    num_trades: u64,
+    // The number of trades in the current candle.
+    num_candle_trades: usize,
+    // Set when the candle was flushed, so the rule starts a new period with the next trade.
+    reset_rule: bool,
+    // Set when the candle was finished by `on_time`, until the next trade starts a new candle.
+    closed_on_time: bool,
+    // The price of the most recent trade, which is the close price of the current candle.
+    last_price: f64,
     _trade_type: PhantomData<T>,
 }
 
@@ -58,9 +96,178 @@ where
             candle: Default::default(),
             aggregation_rule,
             include_trade_that_triggered_rule,
+            num_trades: 0,
+            num_candle_trades: 0,
+            reset_rule: false,
+            closed_on_time: false,
+            last_price: 0.0,
             _trade_type: PhantomData,
         }
     }
+
+    /// Create a new instance like `new`, but starting with the given candle instead of an empty default one,
+    /// e.g. to configure components that keep their state across candles, like the `Vpin`.
+    /// The candle is reset after each finished candle, so it should not contain any trades yet.
+    pub fn with_candle(
+        candle: C,
+        aggregation_rule: R,
+        include_trade_that_triggered_rule: bool,
+    ) -> Self {
+        Self {
+            candle,
+            ..Self::new(aggregation_rule, include_trade_that_triggered_rule)
+        }
+    }
+
+    /// Get a reference to the `AggregationRule`,
+    /// e.g. to inspect which child of an `AnyRule` finished the most recent candle.
+    pub fn aggregation_rule(&self) -> &R {
+        &self.aggregation_rule
+    }
+
+    /// Updates the aggregation state with a new trade, just like `Aggregator::update`,
+    /// but also reports which trade triggered the `AggregationRule` and why.
+    ///
+    /// # Arguments:
+    /// trade: the trade information to add to the aggregation process
+    ///
+    /// # Returns:
+    /// Some `AggregationEvent` only when a new candle has been created,
+    /// otherwise it returns None
+    pub fn update_with_event(&mut self, trade: &T) -> Option<AggregationEvent<C, R::Reason>>
+    where
+        R: TriggerReason,
+    {
+        let candle = self.update(trade)?;
+
+        Some(AggregationEvent {
+            candle,
+            trigger_timestamp: trade.timestamp(),
+            trigger_sequence: self.num_trades - 1,
+            trigger_included: self.include_trade_that_triggered_rule,
+            reason: self.aggregation_rule.trigger_reason(),
+        })
+    }
+
+    /// Updates the aggregation state with a new trade, just like `Aggregator::update`,
+    /// but also emits a synthetic candle for every period of a time based rule that did not contain any trades.
+    /// Each synthetic candle is created by `ModularCandle::fill_gap`, instead of being updated with trades,
+    /// from two zero sized trades at the open and close timestamp of its period,
+    /// carrying forward the close price of the previous candle.
+    /// So the price components (`Open`, `High`, `Low` and `Close`) hold that price
+    /// and the time components (e.g. `OpenTimeStamp` and `CloseTimeStamp`) span the period,
+    /// while the components describing the trades themselves, e.g. `Volume` and `NumTrades`, stay reset,
+    /// just like custom components that don't implement `CandleComponentUpdate::fill_gap`.
+    /// E.g. `AveragePrice` is NaN and `MedianPrice` has no value, as there are no trades.
+    ///
+    /// # Arguments:
+    /// trade: the trade information to add to the aggregation process
+    ///
+    /// # Returns:
+    /// The finished candle followed by the synthetic candles of the empty periods, in chronological order.
+    /// Empty if no candle has been created.
+    pub fn update_filling_gaps(&mut self, trade: &T) -> Vec<C>
+    where
+        R: GapFill,
+        T: SyntheticTrade,
+    {
+        let prev_price = self.last_price;
+        let closed_on_time = self.closed_on_time;
+        if closed_on_time {
+            // Fill the periods from the candle finished by `on_time` on, not from the time it was called
+            self.aggregation_rule.undo_on_time();
+        }
+        let candle = self.update(trade);
+        // The flag is only cleared if the trade has been accepted by the rule.
+        let closed_on_time = closed_on_time && !self.closed_on_time;
+        if candle.is_none() && !closed_on_time {
+            return vec![];
+        }
+        // A candle finished by `on_time` never contains the next trade.
+        let close_price = if self.include_trade_that_triggered_rule && !closed_on_time {
+            trade.price()
+        } else {
+            prev_price
+        };
+
+        let mut out: Vec<C> = candle.into_iter().collect();
+        while let Some((open_ts, close_ts)) =
+            self.aggregation_rule.next_empty_period(trade.timestamp())
+        {
+            let mut candle = C::default();
+            candle.fill_gap(
+                &T::synthetic(open_ts, close_price),
+                &T::synthetic(close_ts, close_price),
+            );
+            out.push(candle);
+        }
+
+        out
+    }
+
+    /// Updates the aggregation state with a new trade, like `Aggregator::update`,
+    /// but splits a trade that crosses the boundary of a volume bucket proportionally,
+    /// so every finished candle contains exactly the volume threshold of the rule, up to floating point precision.
+    /// A remainder that is only the floating point residue of the split is dropped,
+    /// instead of being added to the next candle as a separate trade.
+    /// The part completing the bucket is added to the finished candle,
+    /// while the remainder is added to the next candle, or finishes multiple candles if the trade is large enough.
+    /// Each part is counted as a separate trade by the candle components,
+    /// and `include_trade_that_triggered_rule` has no effect.
+    /// Trades with a size that is not finite can't be split and are ignored.
+    /// Trades without a finite volume per unit of size, e.g. at a price of zero when measured `By::Base`,
+    /// are added to the current candle without being split or counted towards the bucket.
+    ///
+    /// # Arguments:
+    /// trade: the trade information to add to the aggregation process
+    ///
+    /// # Returns:
+    /// The finished candles, in chronological order.
+    /// Empty if no candle has been finished.
+    pub fn update_exact_buckets(&mut self, trade: &T) -> Vec<C>
+    where
+        R: VolumeSplit<T>,
+        T: SplittableTrade,
+    {
+        self.num_trades += 1;
+        if !trade.size().is_finite() || !self.aggregation_rule.accepts(trade) {
+            return vec![];
+        }
+        if self.reset_rule {
+            // The trade is split into the new buckets below, so the rule starts them without its volume.
+            self.aggregation_rule.reset(&trade.with_size(0.0));
+            self.reset_rule = false;
+        }
+        self.closed_on_time = false;
+
+        let sign = if trade.size() < 0.0 { -1.0 } else { 1.0 };
+        let mut remaining = trade.size().abs();
+        let mut out = vec![];
+        while let Some(filled) = self.aggregation_rule.fill_bucket(trade, remaining) {
+            self.candle.update(&trade.with_size(sign * filled));
+            out.push(self.candle.clone());
+            self.candle.reset();
+            self.num_candle_trades = 0;
+
+            remaining -= filled;
+            // A bucket completed without any part of the trade would never use up the remainder
+            if filled == 0.0 {
+                break;
+            }
+            // Don't add the floating point residue of the split to the next candle
+            if remaining <= trade.size().abs() * SPLIT_EPSILON {
+                remaining = 0.0;
+                break;
+            }
+        }
+        if remaining > 0.0 || out.is_empty() {
+            self.candle.update(&trade.with_size(sign * remaining));
+            self.num_candle_trades += 1;
+        }
+        self.last_price = trade.price();
+
+        out
+    }
 }
 
 impl<C, R, T> Aggregator<C, T> for GenericAggregator<C, R, T>
@@ -70,7 +277,22 @@ where
     T: TakerTrade,
 {
     fn update(&mut self, trade: &T) -> Option<C> {
-        if self.aggregation_rule.should_trigger(trade, &self.candle) {
+        self.num_trades += 1;
+        if !self.aggregation_rule.accepts(trade) {
+            return None;
+        }
+
+        // The rule may trigger on the first trade after the candle has been finished by `on_time`,
+        // in which case there is no candle to emit.
+        let closed_on_time = std::mem::take(&mut self.closed_on_time);
+        let triggered = self.aggregation_rule.should_trigger(trade, &self.candle);
+        // After a flush, the rule starts its new period with the first trade of the new candle,
+        // once it has observed the trade, just like a rule reset by a sibling.
+        let flushed = std::mem::take(&mut self.reset_rule);
+        if flushed {
+            self.aggregation_rule.reset(trade);
+        }
+        if triggered && !closed_on_time && !flushed {
             // During some aggregations, the desired behaviour is that the trade that crosses the trigger boundary
             // is included in both the current and next candle.
             // Examples uses include ensuring the close and open price of the current and next candle are equal.
@@ -82,12 +304,20 @@ where
 
             // Create a new candle.
             self.candle.reset();
-            self.candle.update(trade);
+            if self.include_trade_that_triggered_rule {
+                self.candle.carry_over(trade);
+            } else {
+                self.candle.update(trade);
+            }
+            self.num_candle_trades = 1;
+            self.last_price = trade.price();
 
             return Some(candle);
         }
 
         self.candle.update(trade);
+        self.num_candle_trades += 1;
+        self.last_price = trade.price();
 
         None
     }
@@ -95,6 +325,33 @@ where
     fn unfinished_candle(&self) -> &C {
         &self.candle
     }
+
+    fn flush(&mut self) -> Option<C> {
+        if self.num_candle_trades == 0 {
+            return None;
+        }
+        let candle = self.candle.clone();
+
+        self.candle.reset();
+        self.num_candle_trades = 0;
+        self.reset_rule = true;
+
+        Some(candle)
+    }
+
+    fn on_time(&mut self, now: i64) -> Option<C> {
+        if !self.aggregation_rule.should_trigger_on_time(now) || self.num_candle_trades == 0 {
+            return None;
+        }
+        let candle = self.candle.clone();
+
+        // The next trade starts the new candle.
+        self.candle.reset();
+        self.num_candle_trades = 0;
+        self.closed_on_time = true;
+
+        Some(candle)
+    }
 }
 
 #[cfg(test)]
@@ -103,8 +360,8 @@ mod tests {
 
     use super::*;
     use crate::{
-        candle_components::{CandleComponent, CandleComponentUpdate, Close, NumTrades, Open},
-        load_trades_from_csv, ModularCandle, TimeRule, TimestampResolution, Trade, M1,
+        candle_components::{Close, NumTrades, Open},
+        load_trades_from_csv, TimeRule, TimestampResolution, Trade, M1,
     };
 
     #[derive(Default, Debug, Clone, Candle)]
@@ -136,6 +393,467 @@ mod tests {
         assert_eq!(candle_counter, 5953);
     }
 
+    #[test]
+    fn generic_aggregator_update_with_event() {
+        use crate::{
+            candle_components::tests::TRADES, AnyRule, By, TickRule, TriggeredBy, VolumeRule,
+        };
+
+        let rule = AnyRule::new(TickRule::new(2), VolumeRule::new(30.0, By::Quote).unwrap());
+        let mut a = GenericAggregator::<MyCandle, _, Trade>::new(rule, true);
+
+        let events: Vec<_> = TRADES
+            .iter()
+            .filter_map(|t| a.update_with_event(t))
+            .collect();
+        assert_eq!(
+            events
+                .iter()
+                .map(|e| e.trigger_sequence)
+                .collect::<Vec<_>>(),
+            vec![1, 2, 4, 5, 7, 8]
+        );
+
+        let e = &events[0];
+        assert_eq!(e.trigger_timestamp, TRADES[1].timestamp);
+        assert!(e.trigger_included);
+        assert_eq!(e.candle.num_trades(), 2);
+        assert_eq!(e.reason.triggered_by, Some(TriggeredBy::First));
+        assert_eq!(e.reason.first, 2);
+
+        // The triggering trade is included in both candles,
+        // so its volume also starts the next bucket of the volume rule
+        let e = &events[1];
+        assert_eq!(e.trigger_timestamp, TRADES[2].timestamp);
+        assert_eq!(e.candle.num_trades(), 2);
+        assert_eq!(e.candle.open(), TRADES[1].price);
+        assert_eq!(e.reason.triggered_by, Some(TriggeredBy::Second));
+        assert_eq!(e.reason.second, 30.0);
+
+        let e = &events[5];
+        assert_eq!(e.reason.triggered_by, Some(TriggeredBy::Second));
+    }
+
+    #[test]
+    fn generic_aggregator_flush() {
+        use crate::{candle_components::tests::TRADES, TickRule};
+
+        let mut a = GenericAggregator::<MyCandle, _, Trade>::new(TickRule::new(4), false);
+        assert!(a.flush().is_none());
+
+        let candles: Vec<MyCandle> = TRADES.iter().filter_map(|t| a.update(t)).collect();
+        assert_eq!(candles.len(), 2);
+
+        let partial = a.flush().unwrap();
+        assert_eq!(partial.num_trades(), 3);
+        assert_eq!(partial.open(), TRADES[7].price);
+        assert_eq!(partial.close(), TRADES[9].price);
+        assert!(a.flush().is_none());
+
+        // The rule starts its new period with the first trade after flushing,
+        // which is part of the new candle
+        let candles: Vec<MyCandle> = TRADES.iter().filter_map(|t| a.update(t)).collect();
+        assert_eq!(candles.len(), 2);
+        assert_eq!(candles[0].num_trades(), 4);
+        assert_eq!(candles[0].open(), TRADES[0].price);
+    }
+
+    #[test]
+    fn generic_aggregator_update_filling_gaps() {
+        use crate::{
+            candle_components::{
+                AveragePrice, CloseTimeStamp, High, OpenTimeStamp, StdDevPrices, Volume,
+            },
+            CandleComponent, CandleComponentUpdate, MillisecondPeriod,
+        };
+
+        // A custom component without `fill_gap`, counting the trades it is updated with
+        #[derive(Default, Debug, Clone)]
+        struct Updates(f64);
+
+        impl CandleComponent<f64> for Updates {
+            fn value(&self) -> f64 {
+                self.0
+            }
+
+            fn reset(&mut self) {
+                self.0 = 0.0;
+            }
+        }
+
+        impl CandleComponentUpdate<Trade> for Updates {
+            fn update(&mut self, _: &Trade) {
+                self.0 += 1.0;
+            }
+        }
+
+        #[derive(Default, Debug, Clone, Candle)]
+        struct GapCandle {
+            open: Open,
+            high: High,
+            close: Close,
+            volume: Volume,
+            num_trades: NumTrades<u32>,
+            updates: Updates,
+            average_price: AveragePrice,
+            std_dev_prices: StdDevPrices,
+            open_timestamp: OpenTimeStamp<i64>,
+            close_timestamp: CloseTimeStamp<i64>,
+        }
+
+        const T0: i64 = 1_684_677_200_000;
+        let trades: Vec<Trade> = [
+            (0, 100.0),
+            (5, 101.0),
+            (12, 102.0),
+            (47, 103.0),
+            (51, 104.0),
+        ]
+        .iter()
+        .map(|(secs, price)| Trade {
+            timestamp: T0 + secs * 1_000,
+            price: *price,
+            size: 1.0,
+        })
+        .collect();
+
+        let rule = TimeRule::new(
+            MillisecondPeriod::from_non_zero_secs(10),
+            TimestampResolution::Millisecond,
+        );
+        let mut a = GenericAggregator::<GapCandle, _, Trade>::new(rule, false);
+        let out: Vec<Vec<GapCandle>> = trades.iter().map(|t| a.update_filling_gaps(t)).collect();
+        assert_eq!(
+            out.iter().map(|c| c.len()).collect::<Vec<_>>(),
+            vec![0, 0, 1, 3, 1]
+        );
+
+        // The regular candle is followed by two synthetic candles for the empty periods
+        let candles = &out[3];
+        assert_eq!(candles[0].open(), 102.0);
+        assert_eq!(candles[0].volume(), 1.0);
+        assert_eq!(candles[0].num_trades(), 1);
+        assert_eq!(candles[0].average_price(), 102.0);
+        for (i, c) in candles[1..].iter().enumerate() {
+            let open_ts = T0 + (20 + i as i64 * 10) * 1_000;
+            assert_eq!(c.open(), 102.0);
+            assert_eq!(c.high(), 102.0);
+            assert_eq!(c.close(), 102.0);
+            assert_eq!(c.volume(), 0.0);
+            // The synthetic trades don't reach the components describing the trades
+            assert_eq!(c.num_trades(), 0);
+            assert_eq!(c.updates(), 0.0);
+            assert!(c.average_price().is_nan());
+            assert_eq!(c.std_dev_prices(), 0.0);
+            assert_eq!(c.open_timestamp(), open_ts);
+            assert_eq!(c.close_timestamp(), open_ts + 9_999);
+        }
+        assert_eq!(out[4][0].open(), 103.0);
+        assert_eq!(out[4][0].open_timestamp(), T0 + 47_000);
+    }
+
+    #[test]
+    fn generic_aggregator_update_filling_gaps_after_on_time() {
+        use crate::{
+            candle_components::OpenTimeStamp, AggregationRule, AlignedTimeRule, GapFill,
+            MillisecondPeriod,
+        };
+
+        #[derive(Default, Debug, Clone, Candle)]
+        struct GapCandle {
+            num_trades: NumTrades<u32>,
+            open_timestamp: OpenTimeStamp<i64>,
+        }
+
+        const T0: i64 = 1_684_677_200_000;
+        let trade = |secs: i64| Trade {
+            timestamp: T0 + secs * 1_000,
+            price: 100.0,
+            size: 1.0,
+        };
+
+        // The empty periods after the candle finished by `on_time` are filled,
+        // just like without calling `on_time`
+        fn check<R: AggregationRule<GapCandle, Trade> + GapFill>(
+            rule: R,
+            trade: impl Fn(i64) -> Trade,
+        ) {
+            let mut a = GenericAggregator::<GapCandle, _, Trade>::new(rule, false);
+            assert!(a.update_filling_gaps(&trade(0)).is_empty());
+            let candle = a.on_time(T0 + 15_000).unwrap();
+            assert_eq!(candle.num_trades(), 1);
+
+            let candles = a.update_filling_gaps(&trade(47));
+            assert_eq!(
+                candles
+                    .iter()
+                    .map(|c| (c.num_trades(), c.open_timestamp()))
+                    .collect::<Vec<_>>(),
+                vec![(0, T0 + 10_000), (0, T0 + 20_000), (0, T0 + 30_000)]
+            );
+            // The trade starts the candle of the period containing it
+            assert!(a.update_filling_gaps(&trade(49)).is_empty());
+            assert_eq!(a.update_filling_gaps(&trade(55)).len(), 1);
+        }
+
+        let period = MillisecondPeriod::from_non_zero_secs(10);
+        check(
+            TimeRule::new(period, TimestampResolution::Millisecond),
+            trade,
+        );
+        check(
+            AlignedTimeRule::new(period, TimestampResolution::Millisecond),
+            trade,
+        );
+    }
+
+    #[test]
+    fn generic_aggregator_update_exact_buckets() {
+        use crate::{
+            candle_components::{Volume, VolumeSells},
+            By, VolumeRule,
+        };
+
+        #[derive(Default, Debug, Clone, Candle)]
+        struct BucketCandle {
+            volume: Volume,
+            volume_sells: VolumeSells,
+            num_trades: NumTrades<u32>,
+        }
+
+        let trades: Vec<Trade> = [4.0, 3.0, 10.0, 1.0, 25.0, -2.0]
+            .iter()
+            .enumerate()
+            .map(|(i, size)| Trade {
+                timestamp: 1_684_677_200_000 + i as i64 * 1_000,
+                price: 2.0,
+                size: *size,
+            })
+            .collect();
+
+        let rule = VolumeRule::new(5.0, By::Quote).unwrap();
+        let mut a = GenericAggregator::<BucketCandle, _, Trade>::new(rule, false);
+        let out: Vec<Vec<BucketCandle>> =
+            trades.iter().map(|t| a.update_exact_buckets(t)).collect();
+        assert_eq!(
+            out.iter().map(|c| c.len()).collect::<Vec<_>>(),
+            vec![0, 1, 2, 0, 5, 1]
+        );
+        let candles: Vec<&BucketCandle> = out.iter().flatten().collect();
+        assert!(candles.iter().all(|c| c.volume() == 5.0));
+        // The first candle contains the first trade and a part of the second one
+        assert_eq!(candles[0].num_trades(), 2);
+        // The large trade fills the rest of one bucket and four full buckets
+        assert_eq!(candles[3].num_trades(), 3);
+        assert_eq!(candles[4].num_trades(), 1);
+        // The sign of a split trade is kept
+        assert_eq!(candles[8].volume_sells(), 2.0);
+        // The last trade completed the bucket, so there is nothing left
+        assert!(a.flush().is_none());
+
+        // By base, every bucket contains 2 units of base volume, so 4 units of quote volume at a price of 2
+        let rule = VolumeRule::new(2.0, By::Base).unwrap();
+        let mut a = GenericAggregator::<BucketCandle, _, Trade>::new(rule, false);
+        let candles = a.update_exact_buckets(&trades[2]);
+        assert_eq!(candles.len(), 2);
+        assert!(candles.iter().all(|c| c.volume() == 4.0));
+        assert_eq!(a.unfinished_candle().volume(), 2.0);
+    }
+
+    #[test]
+    fn generic_aggregator_update_exact_buckets_fractional_sizes() {
+        use crate::{By, VolumeRule};
+
+        let trade = |i: usize, size: f64| Trade {
+            timestamp: 1_684_677_200_000 + i as i64 * 1_000,
+            price: 2.0,
+            size,
+        };
+
+        // 0.1 + 0.1 + 0.1 slightly exceeds 0.3, which must not leave a tiny part of the third trade
+        let rule = VolumeRule::new(0.3, By::Quote).unwrap();
+        let mut a = GenericAggregator::<MyCandle, _, Trade>::new(rule, false);
+        let candles: Vec<MyCandle> = (0..10)
+            .flat_map(|i| a.update_exact_buckets(&trade(i, 0.1)))
+            .collect();
+        assert_eq!(candles.len(), 3);
+        assert!(candles.iter().all(|c| c.num_trades() == 3));
+        assert_eq!(a.unfinished_candle().num_trades(), 1);
+
+        // 0.7 + 0.2 is slightly less than 0.9, which must still complete the bucket
+        let rule = VolumeRule::new(0.9, By::Quote).unwrap();
+        let mut a = GenericAggregator::<MyCandle, _, Trade>::new(rule, false);
+        assert!(a.update_exact_buckets(&trade(0, 0.7)).is_empty());
+        let candles = a.update_exact_buckets(&trade(1, 0.2));
+        assert_eq!(candles.len(), 1);
+        assert_eq!(candles[0].num_trades(), 2);
+        assert!(a.update_exact_buckets(&trade(2, 0.5)).is_empty());
+        assert_eq!(a.unfinished_candle().num_trades(), 1);
+        assert_eq!(a.flush().unwrap().num_trades(), 1);
+    }
+
+    #[test]
+    fn generic_aggregator_update_exact_buckets_non_finite_sizes() {
+        use crate::{By, VolumeRule};
+
+        let trade = |size: f64| Trade {
+            timestamp: 1_684_677_200_000,
+            price: 2.0,
+            size,
+        };
+
+        let rule = VolumeRule::new(5.0, By::Quote).unwrap();
+        let mut a = GenericAggregator::<MyCandle, _, Trade>::new(rule, false);
+        assert!(a.update_exact_buckets(&trade(2.0)).is_empty());
+        for size in [f64::NAN, f64::INFINITY, f64::NEG_INFINITY] {
+            assert!(a.update_exact_buckets(&trade(size)).is_empty());
+        }
+        assert_eq!(a.unfinished_candle().num_trades(), 1);
+        assert_eq!(a.update_exact_buckets(&trade(3.0)).len(), 1);
+    }
+
+    #[test]
+    fn generic_aggregator_on_time() {
+        use crate::{candle_components::tests::TRADES, MillisecondPeriod};
+
+        const T0: i64 = 1_684_677_200_000;
+        let rule = TimeRule::new(
+            MillisecondPeriod::from_non_zero_secs(15),
+            TimestampResolution::Millisecond,
+        );
+        let mut a = GenericAggregator::<MyCandle, _, Trade>::new(rule, false);
+        // Nothing to finish before the first trade
+        assert!(a.on_time(T0 + 60_000).is_none());
+
+        assert!(a.update(&TRADES[0]).is_none());
+        assert!(a.update(&TRADES[1]).is_none());
+        assert!(a.on_time(T0 + 15_000).is_none());
+
+        let candle = a.on_time(T0 + 15_001).unwrap();
+        assert_eq!(candle.num_trades(), 2);
+        assert_eq!(candle.open(), TRADES[0].price);
+        assert_eq!(candle.close(), TRADES[1].price);
+        // The empty candle is not emitted again
+        assert!(a.on_time(T0 + 30_001).is_none());
+
+        // The period passed during the last call, so the next trade does not emit an empty candle
+        assert!(a.update(&TRADES[3]).is_none());
+        assert!(a.update(&TRADES[4]).is_none());
+        let candle = a.update(&TRADES[6]).unwrap();
+        assert_eq!(candle.num_trades(), 2);
+        assert_eq!(candle.open(), TRADES[3].price);
+        assert_eq!(candle.close(), TRADES[4].price);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn generic_aggregator_checkpoint_round_trip() {
+        use crate::{
+            all_rule, any_rule,
+            candle_components::{
+                High, Low, NumTrades, OpenTimeStamp, StdDevPrices, Volume, Vpin, WeightedPrice,
+            },
+            AlignedTimeRule, By, MillisecondPeriod, RelativePriceRule, TickImbalanceRule, TickRule,
+            VolumeRule,
+        };
+
+        #[derive(Default, Debug, Clone, Candle, serde::Serialize, serde::Deserialize)]
+        struct CheckpointCandle {
+            open: Open,
+            high: High,
+            low: Low,
+            close: Close,
+            volume: Volume,
+            num_trades: NumTrades<u32>,
+            weighted_price: WeightedPrice,
+            std_dev_prices: StdDevPrices,
+            open_timestamp: OpenTimeStamp<i64>,
+            vpin: Vpin,
+        }
+
+        fn bits(c: &CheckpointCandle) -> Vec<u64> {
+            vec![
+                c.open().to_bits(),
+                c.high().to_bits(),
+                c.low().to_bits(),
+                c.close().to_bits(),
+                c.volume().to_bits(),
+                c.num_trades() as u64,
+                c.weighted_price().to_bits(),
+                c.std_dev_prices().to_bits(),
+                c.open_timestamp() as u64,
+                c.vpin().to_bits(),
+            ]
+        }
+
+        // Snapshots the aggregator after every possible number of trades
+        // and verifies that resuming from the snapshot produces the same candles.
+        fn round_trip<R>(aggregator: GenericAggregator<CheckpointCandle, R, Trade>)
+        where
+            R: AggregationRule<CheckpointCandle, Trade>
+                + Clone
+                + serde::Serialize
+                + serde::de::DeserializeOwned,
+        {
+            let trades = load_trades_from_csv("data/Bitstamp_BTCEUR_1M.csv").unwrap();
+
+            let mut uninterrupted = aggregator.clone();
+            let mut expected: Vec<Vec<u64>> = trades
+                .iter()
+                .filter_map(|t| uninterrupted.update(t))
+                .map(|c| bits(&c))
+                .collect();
+            expected.push(bits(&uninterrupted.flush().unwrap()));
+            assert!(expected.len() > 2);
+
+            for split in 0..=trades.len() {
+                let mut a = aggregator.clone();
+                let mut candles: Vec<Vec<u64>> = trades[..split]
+                    .iter()
+                    .filter_map(|t| a.update(t))
+                    .map(|c| bits(&c))
+                    .collect();
+
+                let snapshot = serde_json::to_string(&a).unwrap();
+                let mut a: GenericAggregator<CheckpointCandle, R, Trade> =
+                    serde_json::from_str(&snapshot).unwrap();
+
+                candles.extend(
+                    trades[split..]
+                        .iter()
+                        .filter_map(|t| a.update(t))
+                        .map(|c| bits(&c)),
+                );
+                candles.push(bits(&a.flush().unwrap()));
+                assert_eq!(candles, expected, "split at {split}");
+            }
+        }
+
+        let secs = MillisecondPeriod::from_non_zero_secs;
+        round_trip(GenericAggregator::new(
+            any_rule!(
+                TimeRule::new(secs(10), TimestampResolution::Microsecond),
+                VolumeRule::new(0.05, By::Quote).unwrap(),
+            ),
+            false,
+        ));
+        round_trip(GenericAggregator::new(
+            all_rule!(
+                AlignedTimeRule::new(secs(5), TimestampResolution::Microsecond),
+                TickRule::new(2),
+            ),
+            true,
+        ));
+        round_trip(GenericAggregator::new(
+            RelativePriceRule::new(0.0002).unwrap(),
+            false,
+        ));
+        round_trip(GenericAggregator::new(
+            TickImbalanceRule::new(3.0, 0.5, 3).unwrap(),
+            false,
+        ));
+    }
+
     #[test]
     fn candle_macro() {
         let my_candle = MyCandle::default();
@@ -14,4 +14,34 @@ pub trait CandleComponent<T> {
 pub trait CandleComponentUpdate<T: TakerTrade> {
     /// Updates the state with newest trade information
     fn update(&mut self, trade: &T);
+
+    /// Updates the just reset state with the trade that also finished the previous candle,
+    /// if that trade is included in both candles (see `GenericAggregator::new`).
+    /// The default implementation updates the state like with any other trade,
+    /// while components with a state spanning candles, like the `Vpin`, count the trade only once.
+    #[inline(always)]
+    fn carry_over(&mut self, trade: &T) {
+        self.update(trade);
+    }
+
+    /// Updates the just reset state for a period without any trades,
+    /// e.g. by `GenericAggregator::update_filling_gaps` or for the additional bricks of `RenkoAggregator`.
+    /// The default implementation leaves the state reset,
+    /// which suits the components describing the trades themselves, e.g. `Volume` or `NumTrades`.
+    /// Components of the price or time of a candle, e.g. `Close` or `OpenTimeStamp`, take them from the boundaries.
+    ///
+    /// # Arguments:
+    /// `open`: A zero sized trade at the open timestamp and price of the period
+    /// `close`: A zero sized trade at the close timestamp and price of the period
+    #[inline(always)]
+    fn fill_gap(&mut self, _open: &T, _close: &T) {}
+}
+
+/// A component whose state can be combined with the state of a subsequent candle,
+/// e.g. to build 1 hour candles from finished 1 minute candles without going back to the raw trades.
+pub trait MergeableComponent {
+    /// Merges the state of a subsequent candle into this one,
+    /// as if this component had been updated with the trades of both candles.
+    /// `other` is assumed to contain at least one trade, while `self` may be empty.
+    fn merge(&mut self, other: &Self);
 }
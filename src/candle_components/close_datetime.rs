@@ -0,0 +1,96 @@
+use chrono::{DateTime, Utc};
+
+use crate::{CandleComponent, CandleComponentUpdate, MergeableComponent, TakerTrade};
+
+/// This 'CandleComponent' keeps track of the closing [`DateTime<Utc>`] of a Candle,
+/// which is the time of the last trade.
+/// Trades with a timestamp outside of the range of [`DateTime<Utc>`] are ignored.
+/// Like `NumTrades<u32>`, the type parameter names the value type for the `Candle` macro,
+/// so use it as `CloseDateTime<DateTime<Utc>>` in a candle.
+#[derive(Debug, Default, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CloseDateTime<T> {
+    value: T,
+}
+
+impl CandleComponent<DateTime<Utc>> for CloseDateTime<DateTime<Utc>> {
+    /// Returns the close time of the candle
+    #[inline(always)]
+    fn value(&self) -> DateTime<Utc> {
+        self.value
+    }
+
+    #[inline(always)]
+    fn reset(&mut self) {}
+}
+
+impl<T: TakerTrade> CandleComponentUpdate<T> for CloseDateTime<DateTime<Utc>> {
+    #[inline(always)]
+    fn update(&mut self, trade: &T) {
+        if let Some(value) = trade
+            .timestamp_resolution()
+            .datetime_from_timestamp(trade.timestamp())
+        {
+            self.value = value;
+        }
+    }
+
+    #[inline(always)]
+    fn fill_gap(&mut self, _open: &T, close: &T) {
+        self.update(close);
+    }
+}
+
+impl MergeableComponent for CloseDateTime<DateTime<Utc>> {
+    #[inline(always)]
+    fn merge(&mut self, other: &Self) {
+        self.value = other.value;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    #[test]
+    fn close_datetime() {
+        let mut m = CloseDateTime::<DateTime<Utc>>::default();
+        for t in &crate::candle_components::tests::TRADES {
+            m.update(t);
+        }
+        assert_eq!(
+            m.value(),
+            Utc.with_ymd_and_hms(2023, 5, 21, 13, 54, 50).unwrap()
+        );
+    }
+
+    #[test]
+    fn datetime_candle() {
+        use crate::{
+            candle_components::{tests::TRADES, OpenDateTime},
+            Aggregator, Candle, GenericAggregator, TimeRule, TimestampResolution, Trade, M1,
+        };
+
+        #[derive(Debug, Default, Clone, Candle)]
+        struct DateTimeCandle {
+            open_datetime: OpenDateTime<DateTime<Utc>>,
+            close_datetime: CloseDateTime<DateTime<Utc>>,
+        }
+
+        let rule = TimeRule::new(M1, TimestampResolution::Millisecond);
+        let mut a = GenericAggregator::<DateTimeCandle, _, Trade>::new(rule, false);
+        let candles: Vec<_> = TRADES.iter().filter_map(|t| a.update(t)).collect();
+
+        assert_eq!(candles.len(), 1);
+        assert_eq!(
+            candles[0].open_datetime(),
+            Utc.with_ymd_and_hms(2023, 5, 21, 13, 53, 20).unwrap()
+        );
+        assert_eq!(
+            candles[0].close_datetime(),
+            Utc.with_ymd_and_hms(2023, 5, 21, 13, 54, 20).unwrap()
+        );
+    }
+}
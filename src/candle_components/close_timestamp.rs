@@ -1,4 +1,4 @@
-use crate::{CandleComponent, CandleComponentUpdate, TakerTrade};
+use crate::{CandleComponent, CandleComponentUpdate, MergeableComponent, TakerTrade};
 
 /// This 'CandleComponent' keeps track of the closing timestamp of a Candle, using the
 /// same unit resolution as the underlying input of [`TakerTrade.timestamp()`].
@@ -31,6 +31,18 @@ impl<T: TakerTrade> CandleComponentUpdate<T> for CloseTimeStamp<i64> {
     fn update(&mut self, trade: &T) {
         self.value = trade.timestamp();
     }
+
+    #[inline(always)]
+    fn fill_gap(&mut self, _open: &T, close: &T) {
+        self.update(close);
+    }
+}
+
+impl MergeableComponent for CloseTimeStamp<i64> {
+    #[inline(always)]
+    fn merge(&mut self, other: &Self) {
+        self.value = other.value;
+    }
 }
 
 #[cfg(test)]
@@ -43,6 +55,6 @@ mod tests {
         for t in &crate::candle_components::tests::TRADES {
             m.update(t);
         }
-        assert_eq!(m.value(), 1684677290_000);
+        assert_eq!(m.value(), 1_684_677_290_000);
     }
 }
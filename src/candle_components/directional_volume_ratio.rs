@@ -1,4 +1,4 @@
-use crate::{CandleComponent, CandleComponentUpdate, TakerTrade};
+use crate::{CandleComponent, CandleComponentUpdate, MergeableComponent, TakerTrade};
 
 /// This 'CandleComponent' keeps track of the ratio of buy volume vs total volume
 #[derive(Clone, Debug, Default)]
@@ -31,6 +31,14 @@ impl<T: TakerTrade> CandleComponentUpdate<T> for DirectionalVolumeRatio {
     }
 }
 
+impl MergeableComponent for DirectionalVolumeRatio {
+    #[inline(always)]
+    fn merge(&mut self, other: &Self) {
+        self.volume += other.volume;
+        self.buy_volume += other.buy_volume;
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use round::round;
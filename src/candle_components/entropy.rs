@@ -1,4 +1,4 @@
-use crate::{CandleComponent, CandleComponentUpdate, TakerTrade};
+use crate::{CandleComponent, CandleComponentUpdate, MergeableComponent, TakerTrade};
 
 /// A `CandleComponent` that computes the binary entropy of whether a trade is a buy or a sell.
 #[derive(Default, Debug, Clone)]
@@ -35,3 +35,11 @@ impl<T: TakerTrade> CandleComponentUpdate<T> for Entropy {
         self.total_observed_trades += 1;
     }
 }
+
+impl MergeableComponent for Entropy {
+    #[inline(always)]
+    fn merge(&mut self, other: &Self) {
+        self.buys += other.buys;
+        self.total_observed_trades += other.total_observed_trades;
+    }
+}
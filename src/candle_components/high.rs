@@ -1,4 +1,4 @@
-use crate::{CandleComponent, CandleComponentUpdate, TakerTrade};
+use crate::{CandleComponent, CandleComponentUpdate, MergeableComponent, TakerTrade};
 
 /// This 'CandleComponent' keeps track of the high price
 #[derive(Default, Debug, Clone)]
@@ -15,7 +15,7 @@ impl CandleComponent<f64> for High {
 
     #[inline(always)]
     fn reset(&mut self) {
-        self.high = std::f64::MIN;
+        self.high = f64::MIN;
     }
 }
 
@@ -26,6 +26,21 @@ impl<T: TakerTrade> CandleComponentUpdate<T> for High {
             self.high = trade.price();
         }
     }
+
+    #[inline(always)]
+    fn fill_gap(&mut self, open: &T, close: &T) {
+        self.update(open);
+        self.update(close);
+    }
+}
+
+impl MergeableComponent for High {
+    #[inline(always)]
+    fn merge(&mut self, other: &Self) {
+        if other.high > self.high {
+            self.high = other.high;
+        }
+    }
 }
 
 #[cfg(test)]
@@ -1,4 +1,4 @@
-use crate::{CandleComponent, CandleComponentUpdate, TakerTrade};
+use crate::{CandleComponent, CandleComponentUpdate, MergeableComponent, TakerTrade};
 
 /// This 'CandleComponent' keeps track of the low price
 #[derive(Debug, Clone)]
@@ -34,6 +34,21 @@ impl<T: TakerTrade> CandleComponentUpdate<T> for Low {
             self.low = trade.price();
         }
     }
+
+    #[inline(always)]
+    fn fill_gap(&mut self, open: &T, close: &T) {
+        self.update(open);
+        self.update(close);
+    }
+}
+
+impl MergeableComponent for Low {
+    #[inline(always)]
+    fn merge(&mut self, other: &Self) {
+        if other.low < self.low {
+            self.low = other.low;
+        }
+    }
 }
 
 #[cfg(test)]
@@ -1,4 +1,4 @@
-use crate::{CandleComponent, CandleComponentUpdate, TakerTrade};
+use crate::{CandleComponent, CandleComponentUpdate, MergeableComponent, TakerTrade};
 
 /// Computes the median price from a sorted list of trade prices
 #[derive(Debug, Default, Clone)]
@@ -29,6 +29,13 @@ impl<T: TakerTrade> CandleComponentUpdate<T> for MedianPrice {
     }
 }
 
+impl MergeableComponent for MedianPrice {
+    #[inline(always)]
+    fn merge(&mut self, other: &Self) {
+        self.prices.extend_from_slice(&other.prices);
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
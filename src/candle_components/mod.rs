@@ -4,6 +4,8 @@
 mod average_price;
 mod candle_component_trait;
 mod close;
+#[cfg(feature = "chrono")]
+mod close_datetime;
 mod close_timestamp;
 mod directional_trade_ratio;
 mod directional_volume_ratio;
@@ -11,6 +13,7 @@ mod entropy;
 mod high;
 mod low;
 mod median_price;
+mod notional;
 mod num_trades;
 mod open;
 #[cfg(feature = "chrono")]
@@ -27,8 +30,10 @@ mod vpin;
 mod weighted_price;
 
 pub use average_price::AveragePrice;
-pub use candle_component_trait::{CandleComponent, CandleComponentUpdate};
+pub use candle_component_trait::{CandleComponent, CandleComponentUpdate, MergeableComponent};
 pub use close::Close;
+#[cfg(feature = "chrono")]
+pub use close_datetime::CloseDateTime;
 pub use close_timestamp::CloseTimeStamp;
 pub use directional_trade_ratio::DirectionalTradeRatio;
 pub use directional_volume_ratio::DirectionalVolumeRatio;
@@ -36,6 +41,7 @@ pub use entropy::Entropy;
 pub use high::High;
 pub use low::Low;
 pub use median_price::MedianPrice;
+pub use notional::Notional;
 pub use num_trades::NumTrades;
 pub use open::Open;
 #[cfg(feature = "chrono")]
@@ -52,59 +58,290 @@ pub use vpin::Vpin;
 pub use weighted_price::WeightedPrice;
 
 #[cfg(test)]
-mod tests {
-    use crate::Trade;
+pub(crate) mod tests {
+    use std::marker::PhantomData;
+
+    use super::Notional;
+    use crate::{Candle, ContractSpec, SplittableTrade, TakerTrade, Trade};
+
+    /// A trade of an inverse contract, where each contract is worth 100 units of the quote currency
+    pub(crate) struct InverseTrade(pub(crate) Trade);
+
+    impl TakerTrade for InverseTrade {
+        fn timestamp(&self) -> i64 {
+            self.0.timestamp
+        }
+
+        fn contract_spec(&self) -> ContractSpec {
+            ContractSpec::Inverse { multiplier: 100.0 }
+        }
+
+        fn price(&self) -> f64 {
+            self.0.price
+        }
+
+        fn size(&self) -> f64 {
+            self.0.size
+        }
+    }
+
+    impl SplittableTrade for InverseTrade {
+        fn with_size(&self, size: f64) -> Self {
+            Self(self.0.with_size(size))
+        }
+    }
+
+    /// A candle of `InverseTrade`s
+    #[derive(Debug, Default, Clone, Candle)]
+    pub(crate) struct InverseCandle {
+        notional: Notional,
+        input: PhantomData<InverseTrade>,
+    }
 
     pub const TRADES: [Trade; 10] = [
         Trade {
-            timestamp: 1684677200_000,
+            timestamp: 1_684_677_200_000,
             price: 100.0,
             size: 10.0,
         },
         Trade {
-            timestamp: 1684677210_000,
+            timestamp: 1_684_677_210_000,
             price: 101.0,
             size: -10.0,
         },
         Trade {
-            timestamp: 1684677220_000,
+            timestamp: 1_684_677_220_000,
             price: 100.0,
             size: 20.0,
         },
         Trade {
-            timestamp: 1684677230_000,
+            timestamp: 1_684_677_230_000,
             price: 102.0,
             size: 10.0,
         },
         Trade {
-            timestamp: 1684677240_000,
+            timestamp: 1_684_677_240_000,
             price: 103.0,
             size: 10.0,
         },
         Trade {
-            timestamp: 1684677250_000,
+            timestamp: 1_684_677_250_000,
             price: 104.0,
             size: -20.0,
         },
         Trade {
-            timestamp: 1684677260_000,
+            timestamp: 1_684_677_260_000,
             price: 102.0,
             size: -10.0,
         },
         Trade {
-            timestamp: 1684677270_000,
+            timestamp: 1_684_677_270_000,
             price: 101.0,
             size: 10.0,
         },
         Trade {
-            timestamp: 1684677280_000,
+            timestamp: 1_684_677_280_000,
             price: 102.0,
             size: 30.0,
         },
         Trade {
-            timestamp: 1684677290_000,
+            timestamp: 1_684_677_290_000,
             price: 105.0,
             size: 10.0,
         },
     ];
+
+    mod merge {
+        use trade_aggregation_derive::Candle;
+
+        use super::TRADES;
+        use crate::{candle_components::*, ModularCandle, Trade};
+
+        #[derive(Debug, Default, Clone, Candle)]
+        struct MergeCandle {
+            open: Open,
+            high: High,
+            low: Low,
+            close: Close,
+            volume: Volume,
+            num_trades: NumTrades<u32>,
+            average_price: AveragePrice,
+            weighted_price: WeightedPrice,
+            std_dev_prices: StdDevPrices,
+            std_dev_sizes: StdDevSizes,
+            open_timestamp: OpenTimeStamp<i64>,
+            close_timestamp: CloseTimeStamp<i64>,
+            directional_trade_ratio: DirectionalTradeRatio,
+            directional_volume_ratio: DirectionalVolumeRatio,
+            entropy: Entropy,
+            median_price: MedianPrice,
+            time_velocity: TimeVelocity,
+            volume_buys: VolumeBuys,
+            volume_sells: VolumeSells,
+        }
+
+        // Vpin is not mergeable, which must not prevent deriving the candle.
+        #[derive(Debug, Default, Clone, Candle)]
+        struct VpinCandle {
+            vpin: Vpin,
+        }
+
+        fn candle(trades: &[Trade]) -> MergeCandle {
+            let mut candle = MergeCandle::default();
+            trades.iter().for_each(|t| candle.update(t));
+            candle
+        }
+
+        #[test]
+        fn merge_candles() {
+            let expected = candle(&TRADES);
+            for split in 1..TRADES.len() {
+                // Merging into an empty candle works as well
+                let mut merged = MergeCandle::default();
+                assert!(merged.try_merge(&candle(&TRADES[..split])));
+                assert!(merged.try_merge(&candle(&TRADES[split..])));
+
+                assert_eq!(merged.open(), expected.open());
+                assert_eq!(merged.high(), expected.high());
+                assert_eq!(merged.low(), expected.low());
+                assert_eq!(merged.close(), expected.close());
+                assert_eq!(merged.volume(), expected.volume());
+                assert_eq!(merged.num_trades(), expected.num_trades());
+                assert_eq!(merged.average_price(), expected.average_price());
+                assert_eq!(merged.weighted_price(), expected.weighted_price());
+                assert!((merged.std_dev_prices() - expected.std_dev_prices()).abs() < 1e-9);
+                assert!((merged.std_dev_sizes() - expected.std_dev_sizes()).abs() < 1e-9);
+                assert_eq!(merged.open_timestamp(), expected.open_timestamp());
+                assert_eq!(merged.close_timestamp(), expected.close_timestamp());
+                assert_eq!(
+                    merged.directional_trade_ratio(),
+                    expected.directional_trade_ratio()
+                );
+                assert_eq!(
+                    merged.directional_volume_ratio(),
+                    expected.directional_volume_ratio()
+                );
+                assert_eq!(merged.entropy(), expected.entropy());
+                assert_eq!(merged.median_price(), expected.median_price());
+                assert_eq!(merged.time_velocity(), expected.time_velocity());
+                assert_eq!(merged.volume_buys(), expected.volume_buys());
+                assert_eq!(merged.volume_sells(), expected.volume_sells());
+            }
+        }
+
+        #[test]
+        fn try_merge_candles() {
+            let expected = candle(&TRADES);
+            let mut merged = candle(&TRADES[..4]);
+            assert!(merged.try_merge(&candle(&TRADES[4..])));
+            assert_eq!(merged.num_trades(), expected.num_trades());
+            assert_eq!(merged.close(), expected.close());
+        }
+
+        #[test]
+        fn non_mergeable_candle() {
+            let mut candle = VpinCandle::default();
+            TRADES.iter().for_each(|t| candle.update(t));
+            assert!(candle.vpin() > 0.0);
+
+            // The candle is left unchanged
+            let vpin = candle.vpin();
+            assert!(!candle.try_merge(&VpinCandle::default()));
+            assert_eq!(candle.vpin(), vpin);
+        }
+    }
+
+    mod qualified_paths {
+        use trade_aggregation_derive::Candle;
+
+        use super::TRADES;
+
+        // Neither the components nor the traits are in scope
+        #[derive(Debug, Default, Clone, Candle)]
+        struct QualifiedCandle {
+            num_trades: crate::candle_components::NumTrades<u32>,
+            open_timestamp: crate::candle_components::OpenTimeStamp<i64>,
+            input: std::marker::PhantomData<crate::Trade>,
+        }
+
+        #[test]
+        fn qualified_component_types() {
+            let mut candle = QualifiedCandle::default();
+            TRADES
+                .iter()
+                .for_each(|t| crate::ModularCandle::update(&mut candle, t));
+            let num_trades: u32 = candle.num_trades();
+            let open_timestamp: i64 = candle.open_timestamp();
+            assert_eq!(num_trades, 10);
+            assert_eq!(open_timestamp, TRADES[0].timestamp);
+        }
+
+        // A type of your own named like a component, which the macro must not treat as the `High` component
+        #[derive(Debug, Default, Clone)]
+        struct High(f64);
+
+        impl crate::CandleComponent<f64> for High {
+            fn value(&self) -> f64 {
+                self.0
+            }
+
+            fn reset(&mut self) {}
+        }
+
+        impl crate::CandleComponentUpdate<crate::Trade> for High {
+            fn update(&mut self, trade: &crate::Trade) {
+                self.0 = trade.price;
+            }
+        }
+
+        #[derive(Debug, Default, Clone, Candle)]
+        struct OwnHighCandle {
+            high: High,
+            low: crate::candle_components::Low,
+        }
+
+        impl OwnHighCandle {
+            // Doesn't clash with the derived implementation
+            fn merge(&mut self, other: &Self) {
+                self.high = other.high.clone();
+            }
+        }
+
+        // Tells whether a candle implements `HighLow`, like the `merge_probe` for mergeable components
+        struct HighLowProbe<C>(std::marker::PhantomData<C>);
+
+        trait WithHighLow {
+            fn has_high_low(&self) -> bool {
+                true
+            }
+        }
+
+        impl<C: crate::HighLow> WithHighLow for HighLowProbe<C> {}
+
+        trait NoHighLow {
+            fn has_high_low(&self) -> bool {
+                false
+            }
+        }
+
+        impl<C> NoHighLow for &HighLowProbe<C> {}
+
+        #[test]
+        fn own_component_named_like_a_component() {
+            let mut candle = OwnHighCandle::default();
+            TRADES
+                .iter()
+                .for_each(|t| crate::ModularCandle::update(&mut candle, t));
+            assert_eq!(candle.high(), TRADES[9].price);
+            assert!(!(&HighLowProbe::<OwnHighCandle>(Default::default())).has_high_low());
+            assert!(HighLowProbe::<crate::plot::OhlcCandle>(Default::default()).has_high_low());
+
+            let other = OwnHighCandle {
+                high: High(1.0),
+                ..Default::default()
+            };
+            candle.merge(&other);
+            assert_eq!(candle.high(), 1.0);
+        }
+    }
 }
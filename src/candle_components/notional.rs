@@ -0,0 +1,69 @@
+use crate::{CandleComponent, CandleComponentUpdate, MergeableComponent, TakerTrade};
+
+/// This 'CandleComponent' keeps track of the cumulative notional value of trades,
+/// as determined by the `ContractSpec` of each trade, e.g. `price * size` for linear instruments.
+#[derive(Debug, Default, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Notional {
+    notional: f64,
+}
+
+impl CandleComponent<f64> for Notional {
+    #[inline(always)]
+    fn value(&self) -> f64 {
+        self.notional
+    }
+
+    #[inline(always)]
+    fn reset(&mut self) {
+        self.notional = 0.0;
+    }
+}
+
+impl<T: TakerTrade> CandleComponentUpdate<T> for Notional {
+    #[inline(always)]
+    fn update(&mut self, trade: &T) {
+        self.notional += trade.contract_spec().notional(trade.price(), trade.size())
+    }
+}
+
+impl MergeableComponent for Notional {
+    #[inline(always)]
+    fn merge(&mut self, other: &Self) {
+        self.notional += other.notional;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{candle_components::tests::InverseTrade, ContractSpec, Trade};
+
+    #[test]
+    fn notional() {
+        let mut m = Notional::default();
+        let mut sum: f64 = 0.0;
+        for t in &crate::candle_components::tests::TRADES {
+            sum += t.price * t.size.abs();
+            m.update(t);
+            assert_eq!(m.value(), sum);
+        }
+    }
+
+    #[test]
+    fn notional_contract_spec() {
+        let mut m = Notional::default();
+        // 20 contracts of 100 USD each at a price of 25_000 USD are worth 0.08 BTC
+        m.update(&InverseTrade(Trade {
+            timestamp: 0,
+            price: 25_000.0,
+            size: -20.0,
+        }));
+        assert_eq!(m.value(), 0.08);
+
+        let quanto = ContractSpec::Quanto { multiplier: 0.125 };
+        assert_eq!(quanto.notional(2_000.0, 5.0), 1_250.0);
+        let linear = ContractSpec::Linear { multiplier: 0.5 };
+        assert_eq!(linear.notional(2_000.0, -5.0), 5_000.0);
+    }
+}
@@ -1,4 +1,4 @@
-use crate::{CandleComponent, CandleComponentUpdate, TakerTrade};
+use crate::{CandleComponent, CandleComponentUpdate, MergeableComponent, TakerTrade};
 
 /// This 'CandleComponent' keeps track of the opening price of a Candle
 #[derive(Debug, Clone)]
@@ -39,6 +39,21 @@ impl<T: TakerTrade> CandleComponentUpdate<T> for Open {
             self.init = false;
         }
     }
+
+    #[inline(always)]
+    fn fill_gap(&mut self, open: &T, _close: &T) {
+        self.update(open);
+    }
+}
+
+impl MergeableComponent for Open {
+    #[inline(always)]
+    fn merge(&mut self, other: &Self) {
+        if self.init {
+            self.value = other.value;
+            self.init = other.init;
+        }
+    }
 }
 
 #[cfg(test)]
@@ -1,16 +1,19 @@
-use chrono::{DateTime, TimeZone, Utc};
+use chrono::{DateTime, Utc};
 
-use crate::{CandleComponent, CandleComponentUpdate, TakerTrade, TimestampResolution};
+use crate::{CandleComponent, CandleComponentUpdate, MergeableComponent, TakerTrade};
 
 /// This 'CandleComponent' keeps track of the opening [`DateTime<Utc>`] of a Candle.
+/// Trades with a timestamp outside of the range of [`DateTime<Utc>`] are ignored.
+/// Like `NumTrades<u32>`, the type parameter names the value type for the `Candle` macro,
+/// so use it as `OpenDateTime<DateTime<Utc>>` in a candle.
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
-pub struct OpenDateTime {
+pub struct OpenDateTime<T> {
     init: bool,
-    value: DateTime<Utc>,
+    value: T,
 }
 
-impl Default for OpenDateTime {
+impl<T: Default> Default for OpenDateTime<T> {
     fn default() -> Self {
         Self {
             init: true,
@@ -19,8 +22,8 @@ impl Default for OpenDateTime {
     }
 }
 
-impl CandleComponent<DateTime<Utc>> for OpenDateTime {
-    /// Returns the open price of the candle
+impl CandleComponent<DateTime<Utc>> for OpenDateTime<DateTime<Utc>> {
+    /// Returns the open time of the candle
     #[inline(always)]
     fn value(&self) -> DateTime<Utc> {
         self.value
@@ -32,33 +35,53 @@ impl CandleComponent<DateTime<Utc>> for OpenDateTime {
     }
 }
 
-impl<T: TakerTrade> CandleComponentUpdate<T> for OpenDateTime {
-    /// Only update the open price if this module is in init mode
+impl<T: TakerTrade> CandleComponentUpdate<T> for OpenDateTime<DateTime<Utc>> {
+    /// Only update the open time if this module is in init mode
     #[inline(always)]
     fn update(&mut self, trade: &T) {
-        if self.init {
-            let stamp = trade.timestamp();
-            self.value = match trade.timestamp_resolution() {
-                TimestampResolution::Second => Utc.timestamp(stamp, 0),
-                TimestampResolution::Millisecond => Utc.timestamp_millis(trade.timestamp()),
-                TimestampResolution::Microsecond => Utc.timestamp_nanos(stamp * 1000),
-                TimestampResolution::Nanosecond => Utc.timestamp_nanos(stamp),
-            };
+        if !self.init {
+            return;
+        }
+        if let Some(value) = trade
+            .timestamp_resolution()
+            .datetime_from_timestamp(trade.timestamp())
+        {
+            self.value = value;
             self.init = false;
         }
     }
+
+    #[inline(always)]
+    fn fill_gap(&mut self, open: &T, _close: &T) {
+        self.update(open);
+    }
+}
+
+impl MergeableComponent for OpenDateTime<DateTime<Utc>> {
+    #[inline(always)]
+    fn merge(&mut self, other: &Self) {
+        if self.init {
+            self.value = other.value;
+            self.init = other.init;
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
+    use chrono::TimeZone;
+
     use super::*;
 
     #[test]
     fn open_datetime() {
-        let mut m = OpenDateTime::default();
+        let mut m = OpenDateTime::<DateTime<Utc>>::default();
         for t in &crate::candle_components::tests::TRADES {
             m.update(t);
         }
-        assert_eq!(m.value(), Utc.ymd(1985, 11, 5).and_hms(0, 53, 20));
+        assert_eq!(
+            m.value(),
+            Utc.with_ymd_and_hms(2023, 5, 21, 13, 53, 20).unwrap()
+        );
     }
 }
@@ -1,4 +1,4 @@
-use crate::{CandleComponent, CandleComponentUpdate, TakerTrade};
+use crate::{CandleComponent, CandleComponentUpdate, MergeableComponent, TakerTrade};
 
 /// This 'CandleComponent' keeps track of the opening timestamp of a Candle, using the
 /// same unit resolution as the underlying input of [`TakerTrade.timestamp()`].
@@ -38,6 +38,21 @@ impl<T: TakerTrade> CandleComponentUpdate<T> for OpenTimeStamp<i64> {
             self.init = false;
         }
     }
+
+    #[inline(always)]
+    fn fill_gap(&mut self, open: &T, _close: &T) {
+        self.update(open);
+    }
+}
+
+impl MergeableComponent for OpenTimeStamp<i64> {
+    #[inline(always)]
+    fn merge(&mut self, other: &Self) {
+        if self.init {
+            self.value = other.value;
+            self.init = other.init;
+        }
+    }
 }
 
 #[cfg(test)]
@@ -1,4 +1,7 @@
-use crate::{welford_online::WelfordOnline, CandleComponent, CandleComponentUpdate, TakerTrade};
+use crate::{
+    welford_online::WelfordOnline, CandleComponent, CandleComponentUpdate, MergeableComponent,
+    TakerTrade,
+};
 
 /// This 'CandleComponent' keeps track of the standard deviation in the trade sizes
 #[derive(Debug, Clone)]
@@ -32,3 +35,10 @@ impl<T: TakerTrade> CandleComponentUpdate<T> for StdDevSizes {
         self.welford.add(trade.size());
     }
 }
+
+impl MergeableComponent for StdDevSizes {
+    #[inline(always)]
+    fn merge(&mut self, other: &Self) {
+        self.welford.merge(&other.welford);
+    }
+}
@@ -1,4 +1,4 @@
-use crate::{CandleComponent, CandleComponentUpdate, TakerTrade, TimestampResolution};
+use crate::{CandleComponent, CandleComponentUpdate, MergeableComponent, TakerTrade};
 
 /// Measures the velocity of candle creation based on the formula:
 /// 1.0 / t  , where t is measured in seconds
@@ -42,23 +42,36 @@ impl CandleComponent<f64> for TimeVelocity {
 impl<T: TakerTrade> CandleComponentUpdate<T> for TimeVelocity {
     #[inline(always)]
     fn update(&mut self, trade: &T) {
-        let div = match trade.timestamp_resolution() {
-            TimestampResolution::Millisecond => 1_000,
-            TimestampResolution::Microsecond => 1_000_000,
-            TimestampResolution::Nanosecond => 1_000_000_000,
-        };
+        let div = trade.timestamp_resolution().units_per_second();
         if self.init {
             self.init_time = trade.timestamp() / div;
             self.init = false;
         }
         self.last_time = trade.timestamp() / div;
     }
+
+    #[inline(always)]
+    fn fill_gap(&mut self, open: &T, close: &T) {
+        self.update(open);
+        self.update(close);
+    }
+}
+
+impl MergeableComponent for TimeVelocity {
+    #[inline(always)]
+    fn merge(&mut self, other: &Self) {
+        if self.init {
+            self.init_time = other.init_time;
+            self.init = other.init;
+        }
+        self.last_time = other.last_time;
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::candle_components::tests::TRADES;
+    use crate::{candle_components::tests::TRADES, TimestampResolution, Trade};
 
     #[test]
     fn time_velocity() {
@@ -68,4 +81,33 @@ mod tests {
         }
         assert_eq!(round::round(comp.value(), 3), 0.011);
     }
+
+    #[test]
+    fn time_velocity_second_resolution() {
+        struct SecondTrade(Trade);
+
+        impl TakerTrade for SecondTrade {
+            fn timestamp(&self) -> i64 {
+                self.0.timestamp / 1_000
+            }
+
+            fn timestamp_resolution(&self) -> TimestampResolution {
+                TimestampResolution::Second
+            }
+
+            fn price(&self) -> f64 {
+                self.0.price
+            }
+
+            fn size(&self) -> f64 {
+                self.0.size
+            }
+        }
+
+        let mut comp = TimeVelocity::default();
+        for t in TRADES.iter() {
+            comp.update(&SecondTrade(*t));
+        }
+        assert_eq!(round::round(comp.value(), 3), 0.011);
+    }
 }
@@ -1,4 +1,4 @@
-use crate::{CandleComponent, CandleComponentUpdate, TakerTrade};
+use crate::{CandleComponent, CandleComponentUpdate, MergeableComponent, TakerTrade};
 
 /// A `CandleComponent` that gathers all observed trades and returns them.
 /// Be careful, the `value` method clones the inner vector,
@@ -41,6 +41,16 @@ where
     }
 }
 
+impl<T> MergeableComponent for Trades<T>
+where
+    T: TakerTrade + Clone,
+{
+    #[inline(always)]
+    fn merge(&mut self, other: &Self) {
+        self.trades.extend_from_slice(&other.trades);
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
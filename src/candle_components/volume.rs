@@ -1,6 +1,8 @@
-use crate::{CandleComponent, CandleComponentUpdate, TakerTrade};
+use crate::{CandleComponent, CandleComponentUpdate, MergeableComponent, TakerTrade};
 
-/// This 'CandleComponent' keeps track of the cumulative volume of trades.
+/// This 'CandleComponent' keeps track of the cumulative volume of trades,
+/// which is the sum of the absolute trade sizes in contracts, regardless of the `ContractSpec`.
+/// Use the 'Notional' component for the traded value of any contract type.
 #[derive(Debug, Default, Clone)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Volume {
@@ -26,6 +28,13 @@ impl<T: TakerTrade> CandleComponentUpdate<T> for Volume {
     }
 }
 
+impl MergeableComponent for Volume {
+    #[inline(always)]
+    fn merge(&mut self, other: &Self) {
+        self.volume += other.volume;
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1,4 +1,4 @@
-use crate::{CandleComponent, CandleComponentUpdate, TakerTrade};
+use crate::{CandleComponent, CandleComponentUpdate, MergeableComponent, TakerTrade};
 
 /// This 'CandleComponent' keeps track of the cumulative buy volume of trades.
 #[derive(Debug, Default, Clone)]
@@ -27,3 +27,10 @@ impl<T: TakerTrade> CandleComponentUpdate<T> for VolumeBuys {
         }
     }
 }
+
+impl MergeableComponent for VolumeBuys {
+    #[inline(always)]
+    fn merge(&mut self, other: &Self) {
+        self.buy_volume += other.buy_volume;
+    }
+}
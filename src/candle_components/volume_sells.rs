@@ -1,4 +1,4 @@
-use crate::{CandleComponent, CandleComponentUpdate, TakerTrade};
+use crate::{CandleComponent, CandleComponentUpdate, MergeableComponent, TakerTrade};
 
 /// This 'CandleComponent' keeps track of the cumulative volume of trades.
 #[derive(Debug, Default, Clone)]
@@ -27,3 +27,10 @@ impl<T: TakerTrade> CandleComponentUpdate<T> for VolumeSells {
         }
     }
 }
+
+impl MergeableComponent for VolumeSells {
+    #[inline(always)]
+    fn merge(&mut self, other: &Self) {
+        self.sell_volume += other.sell_volume;
+    }
+}
@@ -1,23 +1,323 @@
+use std::collections::VecDeque;
+
 use super::{CandleComponent, CandleComponentUpdate};
-use crate::TakerTrade;
+use crate::{Error, Result, TakerTrade, SPLIT_EPSILON};
 
-#[derive(Debug, Default, Clone)]
+/// This 'CandleComponent' computes the volume-synchronized probability of informed trading (VPIN).
+/// Trade volume is sorted into equally sized volume buckets, where each trade is classified
+/// as buy or sell volume by the sign of its size.
+/// If a trade does not fit into the current bucket, the excess volume is carried into the next bucket(s).
+/// The value is the average absolute order imbalance over the most recent `window_len` completed buckets,
+/// normalized by the bucket volume: sum(|V_buy - V_sell|) / (n * V_bucket)
+///
+/// The bucket history is not cleared when the candle is reset,
+/// so the rolling window spans across candles.
+/// Before the first bucket is completed, the value is NaN.
+/// Trades with a non-finite size are ignored.
+///
+/// A derived candle creates its components with `Default`, which is a bucket volume of 1.0
+/// and a window of 50 buckets. To configure the buckets, create the candle with a `Vpin` from `Vpin::new`
+/// and pass it to `GenericAggregator::with_candle`, as resetting the candle keeps the configuration.
+///
+/// When the trade that triggered the rule is included in both candles (see `GenericAggregator::new`),
+/// it is only counted once, as it is carried over to the next candle through `CandleComponentUpdate::carry_over`.
+#[derive(Debug, Clone)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
-pub struct Vpin {}
+pub struct Vpin {
+    // The volume each bucket needs to contain before it is completed
+    bucket_volume: f64,
+    // The maximum number of completed buckets taken into account
+    window_len: usize,
+    // The volume filled in the current (incomplete) bucket
+    bucket_filled: f64,
+    // The buy volume minus the sell volume of the current (incomplete) bucket
+    bucket_imbalance: f64,
+    // The absolute order imbalances of the most recently completed buckets
+    imbalances: VecDeque<f64>,
+}
+
+impl Default for Vpin {
+    /// A bucket volume of 1.0 and a window of 50 buckets
+    fn default() -> Self {
+        Self::new(1.0, 50).expect("The default parameters are valid")
+    }
+}
+
+impl Vpin {
+    /// Create a new instance
+    ///
+    /// # Arguments:
+    /// `bucket_volume`: The volume each bucket needs to contain before it is completed.
+    /// `window_len`: The number of completed buckets in the rolling window.
+    ///
+    pub fn new(bucket_volume: f64, window_len: usize) -> Result<Self> {
+        if !bucket_volume.is_finite() || bucket_volume <= 0.0 || window_len == 0 {
+            return Err(Error::InvalidParam);
+        }
+        Ok(Self {
+            bucket_volume,
+            window_len,
+            bucket_filled: 0.0,
+            bucket_imbalance: 0.0,
+            imbalances: VecDeque::with_capacity(window_len),
+        })
+    }
+
+    fn complete_bucket(&mut self) {
+        self.complete_bucket_with(self.bucket_imbalance.abs());
+        self.bucket_filled = 0.0;
+        self.bucket_imbalance = 0.0;
+    }
+
+    fn complete_bucket_with(&mut self, imbalance: f64) {
+        if self.imbalances.len() == self.window_len {
+            self.imbalances.pop_front();
+        }
+        self.imbalances.push_back(imbalance);
+    }
+}
 
 impl CandleComponent<f64> for Vpin {
+    #[inline(always)]
     fn value(&self) -> f64 {
-        todo!()
+        self.imbalances.iter().sum::<f64>() / (self.imbalances.len() as f64 * self.bucket_volume)
     }
 
-    fn reset(&mut self) {
-        todo!()
-    }
+    /// The bucket history is kept, so the rolling window spans across candles
+    #[inline(always)]
+    fn reset(&mut self) {}
 }
 
 impl<T: TakerTrade> CandleComponentUpdate<T> for Vpin {
     #[inline(always)]
-    fn update(&mut self, _: &T) {
-        todo!()
+    fn update(&mut self, trade: &T) {
+        if !trade.size().is_finite() {
+            return;
+        }
+
+        let sign = if trade.size() > 0.0 { 1.0 } else { -1.0 };
+        let size = trade.size().abs();
+        let remaining = self.bucket_volume - self.bucket_filled;
+        // A bucket within the floating point residue of its volume is complete,
+        // e.g. a bucket of 0.9 after 0.7 and 0.2
+        if size < remaining - self.bucket_volume * SPLIT_EPSILON {
+            self.bucket_filled += size;
+            self.bucket_imbalance += sign * size;
+            return;
+        }
+        self.bucket_imbalance += sign * remaining;
+        self.complete_bucket();
+
+        // The rest fills whole buckets of one side, of which only the last `window_len` are kept
+        let rest = (size - remaining).max(0.0);
+        let mut excess = rest % self.bucket_volume;
+        // Ignore the floating point residue of the remainder, e.g. 0.3 fills three buckets of 0.1
+        if excess > self.bucket_volume * (1.0 - SPLIT_EPSILON) {
+            excess = 0.0;
+        }
+        let full_buckets = ((rest - excess) / self.bucket_volume).round();
+        if excess < self.bucket_volume * SPLIT_EPSILON {
+            excess = 0.0;
+        }
+        for _ in 0..(full_buckets.min(self.window_len as f64) as usize) {
+            self.complete_bucket_with(self.bucket_volume);
+        }
+        self.bucket_filled = excess;
+        self.bucket_imbalance = sign * excess;
+    }
+
+    /// The trade has already been counted by the previous candle
+    #[inline(always)]
+    fn carry_over(&mut self, _trade: &T) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use round::round;
+
+    use super::*;
+    use crate::{
+        candle_components::{tests::TRADES, NumTrades},
+        Aggregator, Candle, GenericAggregator, TickRule, Trade,
+    };
+
+    #[test]
+    fn vpin_invalid_params() {
+        assert!(Vpin::new(0.0, 3).is_err());
+        assert!(Vpin::new(-1.0, 3).is_err());
+        assert!(Vpin::new(20.0, 0).is_err());
+        assert!(Vpin::new(f64::NAN, 3).is_err());
+        assert!(Vpin::new(f64::INFINITY, 3).is_err());
+    }
+
+    #[test]
+    fn vpin() {
+        let mut m = Vpin::new(20.0, 3).unwrap();
+        // Hand-computed bucket imbalances with a bucket volume of 20:
+        // [0, 20, 20, 20, 0, 20, 20] where the trade of size 30 fills two buckets.
+        let expected = [
+            f64::NAN,
+            0.0,
+            0.5,
+            0.5,
+            0.6667,
+            1.0,
+            1.0,
+            0.6667,
+            0.6667,
+            0.6667,
+        ];
+        for (t, e) in TRADES.iter().zip(expected) {
+            m.update(t);
+            if e.is_nan() {
+                assert!(m.value().is_nan());
+            } else {
+                assert_eq!(round(m.value(), 4), e);
+            }
+        }
+    }
+
+    #[test]
+    fn vpin_reset_keeps_buckets() {
+        let mut m = Vpin::new(20.0, 3).unwrap();
+        let mut reference = Vpin::new(20.0, 3).unwrap();
+        for t in TRADES.iter() {
+            m.update(t);
+            m.reset();
+            reference.update(t);
+            assert_eq!(m.value().to_bits(), reference.value().to_bits());
+        }
+    }
+
+    #[test]
+    fn vpin_large_trade_fills_multiple_buckets() {
+        let mut m = Vpin::new(10.0, 5).unwrap();
+        m.update(&TRADES[8]);
+        // 30 units of buy volume complete three buckets
+        assert_eq!(m.imbalances.len(), 3);
+        assert_eq!(m.value(), 1.0);
+        assert_eq!(m.bucket_filled, 0.0);
+    }
+
+    #[test]
+    fn vpin_huge_trade() {
+        let mut m = Vpin::new(1.0, 5).unwrap();
+        m.update(&Trade {
+            timestamp: 0,
+            price: 100.0,
+            size: 0.5,
+        });
+        // Only the most recent buckets of the window are completed one by one
+        m.update(&Trade {
+            timestamp: 0,
+            price: 100.0,
+            size: -1e20,
+        });
+        assert_eq!(m.imbalances.len(), 5);
+        assert_eq!(m.value(), 1.0);
+        assert_eq!(m.bucket_filled, 0.0);
+    }
+
+    #[test]
+    fn vpin_default() {
+        let m = Vpin::default();
+        assert_eq!(m.bucket_volume, 1.0);
+        assert_eq!(m.window_len, 50);
+    }
+
+    #[test]
+    fn vpin_floating_point_residue() {
+        let trade = |size: f64| Trade {
+            timestamp: 0,
+            price: 100.0,
+            size,
+        };
+
+        // 0.3 % 0.1 leaves almost a whole bucket, which is the residue of the third bucket
+        let mut m = Vpin::new(0.1, 5).unwrap();
+        m.update(&trade(0.3));
+        assert_eq!(m.imbalances.len(), 3);
+        assert_eq!(m.bucket_filled, 0.0);
+
+        // 0.7 and 0.2 complete a bucket of 0.9, even though their sum is slightly less
+        let mut m = Vpin::new(0.9, 5).unwrap();
+        m.update(&trade(0.7));
+        m.update(&trade(-0.2));
+        assert_eq!(m.imbalances.len(), 1);
+        assert_eq!(m.bucket_filled, 0.0);
+    }
+
+    #[test]
+    fn vpin_non_finite_size() {
+        let mut m = Vpin::new(10.0, 5).unwrap();
+        m.update(&Trade {
+            timestamp: 0,
+            price: 100.0,
+            size: f64::INFINITY,
+        });
+        m.update(&Trade {
+            timestamp: 0,
+            price: 100.0,
+            size: f64::NAN,
+        });
+        assert!(m.value().is_nan());
+        assert_eq!(m.bucket_filled, 0.0);
+    }
+
+    #[derive(Debug, Default, Clone, Candle)]
+    struct VpinCandle {
+        vpin: Vpin,
+        num_trades: NumTrades<u32>,
+    }
+
+    fn vpin_candle() -> VpinCandle {
+        VpinCandle {
+            vpin: Vpin::new(20.0, 3).unwrap(),
+            num_trades: NumTrades::default(),
+        }
+    }
+
+    #[test]
+    fn vpin_triggering_trade_counted_once() {
+        let mut reference = Vpin::new(20.0, 3).unwrap();
+        for t in TRADES.iter() {
+            reference.update(t);
+        }
+
+        // Both candles contain the trade that triggered the rule
+        let mut aggregator = GenericAggregator::<VpinCandle, _, Trade>::with_candle(
+            vpin_candle(),
+            TickRule::new(3),
+            true,
+        );
+        let candles: Vec<VpinCandle> = TRADES.iter().filter_map(|t| aggregator.update(t)).collect();
+        // Apart from the first one, each candle starts with the trade that finished the previous one
+        assert_eq!(
+            candles.iter().map(|c| c.num_trades()).collect::<Vec<_>>(),
+            vec![3, 4, 4]
+        );
+        assert_eq!(
+            aggregator.unfinished_candle().vpin().to_bits(),
+            reference.value().to_bits()
+        );
+    }
+
+    #[test]
+    fn vpin_identical_trades_across_candles() {
+        // Identical trades are distinct trades, even if a candle ends between them
+        let trade = Trade {
+            timestamp: 0,
+            price: 100.0,
+            size: 5.0,
+        };
+        let mut aggregator = GenericAggregator::<VpinCandle, _, Trade>::with_candle(
+            vpin_candle(),
+            TickRule::new(2),
+            false,
+        );
+        for _ in 0..4 {
+            aggregator.update(&trade);
+        }
+        assert_eq!(aggregator.unfinished_candle().vpin(), 1.0);
     }
 }
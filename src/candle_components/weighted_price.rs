@@ -1,4 +1,4 @@
-use crate::{CandleComponent, CandleComponentUpdate, TakerTrade};
+use crate::{CandleComponent, CandleComponentUpdate, MergeableComponent, TakerTrade};
 
 /// This 'CandleComponent' keeps track of the volume weighted price
 #[derive(Debug, Default, Clone)]
@@ -28,6 +28,14 @@ impl<T: TakerTrade> CandleComponentUpdate<T> for WeightedPrice {
     }
 }
 
+impl MergeableComponent for WeightedPrice {
+    #[inline(always)]
+    fn merge(&mut self, other: &Self) {
+        self.total_weights += other.total_weights;
+        self.weighted_sum += other.weighted_sum;
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
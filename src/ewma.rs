@@ -0,0 +1,47 @@
+/// Exponentially weighted moving average
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Ewma {
+    alpha: f64,
+    value: f64,
+}
+
+impl Ewma {
+    /// Create a new instance with the smoothing factor alpha = 2 / (span + 1),
+    /// starting out at the `initial` value
+    pub fn new(span: usize, initial: f64) -> Self {
+        Self {
+            alpha: 2.0 / (span as f64 + 1.0),
+            value: initial,
+        }
+    }
+
+    // value returns the current average
+    pub fn value(&self) -> f64 {
+        self.value
+    }
+
+    // add updates the average with a new observation
+    pub fn add(&mut self, val: f64) {
+        self.value = self.alpha * val + (1.0 - self.alpha) * self.value;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ewma() {
+        let mut ewma = Ewma::new(3, 1.0);
+        ewma.add(3.0);
+        assert_eq!(ewma.value(), 2.0);
+        ewma.add(0.0);
+        assert_eq!(ewma.value(), 1.0);
+
+        // A span of 1 only remembers the last observation
+        let mut ewma = Ewma::new(1, 1.0);
+        ewma.add(5.0);
+        assert_eq!(ewma.value(), 5.0);
+    }
+}
@@ -0,0 +1,154 @@
+use crate::Ohlc;
+
+/// A Heikin-Ashi candle, as created by the `HeikinAshi` transformer
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HeikinAshiCandle {
+    /// The average of the open and close of the previous Heikin-Ashi candle
+    pub open: f64,
+
+    /// The maximum of the high of the input candle and the Heikin-Ashi open and close
+    pub high: f64,
+
+    /// The minimum of the low of the input candle and the Heikin-Ashi open and close
+    pub low: f64,
+
+    /// The average of the open, high, low and close of the input candle
+    pub close: f64,
+}
+
+impl Ohlc for HeikinAshiCandle {
+    fn open(&self) -> f64 {
+        self.open
+    }
+
+    fn high(&self) -> f64 {
+        self.high
+    }
+
+    fn low(&self) -> f64 {
+        self.low
+    }
+
+    fn close(&self) -> f64 {
+        self.close
+    }
+}
+
+/// Transforms a stream of finished candles into Heikin-Ashi candles,
+/// e.g. as a post-processing stage of the candles emitted by a `GenericAggregator`.
+/// As each Heikin-Ashi candle depends on the previous one, the candles have to be passed in order.
+/// The open of the first Heikin-Ashi candle is the average of the open and close of its input candle.
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HeikinAshi {
+    // The previously created Heikin-Ashi candle
+    previous: Option<HeikinAshiCandle>,
+}
+
+impl HeikinAshi {
+    /// Create a new instance, without any previous candle
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Transforms the next finished candle
+    ///
+    /// # Arguments:
+    /// candle: The finished candle, exposing its open, high, low and close
+    ///
+    /// # Returns:
+    /// The Heikin-Ashi candle of the input candle
+    pub fn update<C: Ohlc>(&mut self, candle: &C) -> HeikinAshiCandle {
+        let close = (candle.open() + candle.high() + candle.low() + candle.close()) / 4.0;
+        let open = match self.previous {
+            Some(prev) => (prev.open + prev.close) / 2.0,
+            None => (candle.open() + candle.close()) / 2.0,
+        };
+        let ha = HeikinAshiCandle {
+            open,
+            high: candle.high().max(open).max(close),
+            low: candle.low().min(open).min(close),
+            close,
+        };
+        self.previous = Some(ha);
+
+        ha
+    }
+
+    /// The most recently created Heikin-Ashi candle, if any
+    pub fn previous(&self) -> Option<&HeikinAshiCandle> {
+        self.previous.as_ref()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        candle_components::tests::TRADES, plot::OhlcCandle, Aggregator, GenericAggregator,
+        TickRule, Trade,
+    };
+
+    #[test]
+    fn heikin_ashi() {
+        let input = [
+            HeikinAshiCandle {
+                open: 100.0,
+                high: 104.0,
+                low: 98.0,
+                close: 102.0,
+            },
+            HeikinAshiCandle {
+                open: 102.0,
+                high: 103.0,
+                low: 90.0,
+                close: 93.0,
+            },
+        ];
+        let mut ha = HeikinAshi::new();
+        assert_eq!(
+            ha.update(&input[0]),
+            HeikinAshiCandle {
+                open: 101.0,
+                high: 104.0,
+                low: 98.0,
+                close: 101.0,
+            }
+        );
+        // The open is the average of the previous Heikin-Ashi open and close,
+        // which lies above the high of the input candle
+        assert_eq!(
+            ha.update(&input[1]),
+            HeikinAshiCandle {
+                open: 101.0,
+                high: 103.0,
+                low: 90.0,
+                close: 97.0,
+            }
+        );
+    }
+
+    #[test]
+    fn heikin_ashi_after_aggregator() {
+        // The `Candle` macro implements `Ohlc` for candles with open, high, low and close components
+        let mut aggregator =
+            GenericAggregator::<OhlcCandle, _, Trade>::new(TickRule::new(3), false);
+        let mut ha = HeikinAshi::new();
+
+        let candles: Vec<HeikinAshiCandle> = TRADES
+            .iter()
+            .filter_map(|t| aggregator.update(t))
+            .map(|c| ha.update(&c))
+            .collect();
+        assert_eq!(candles.len(), 3);
+        for pair in candles.windows(2) {
+            assert_eq!(pair[1].open, (pair[0].open + pair[0].close) / 2.0);
+        }
+        for c in candles.iter() {
+            assert!(c.high >= c.open.max(c.close));
+            assert!(c.low <= c.open.min(c.close));
+        }
+        assert_eq!(ha.previous(), candles.last());
+    }
+}
@@ -0,0 +1,266 @@
+use crate::{BrickDirection, Error, Result, TakerTrade};
+
+/// The price movement against the current line of a `KagiAggregator` which reverses it
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum KagiReversal {
+    /// A fixed price distance, e.g. 10.0
+    Absolute(f64),
+
+    /// A fixed fraction of the extreme price of the current line, e.g. 0.04 for 4%
+    Relative(f64),
+}
+
+impl KagiReversal {
+    /// The reversal amount at the given extreme price
+    fn amount(&self, price: f64) -> f64 {
+        match self {
+            KagiReversal::Absolute(amount) => *amount,
+            KagiReversal::Relative(fraction) => fraction * price,
+        }
+    }
+}
+
+/// A vertical line of a kagi chart
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct KagiLine {
+    /// The direction of the line
+    pub direction: BrickDirection,
+
+    /// The price at which the line starts, which is the end of the previous line
+    pub start: f64,
+
+    /// The most extreme price of the line
+    pub end: f64,
+
+    /// Whether the line is thick (yang) at its end, otherwise it is thin (yin).
+    /// The line turns thick once it rises above the previous shoulder,
+    /// and thin once it falls below the previous waist.
+    pub yang: bool,
+
+    /// The timestamp of the trade that started the line, in the unit of the trade timestamps
+    pub start_timestamp: i64,
+
+    /// The timestamp of the trade that reached the end of the line, in the unit of the trade timestamps
+    pub end_timestamp: i64,
+}
+
+/// Creates the lines of a kagi chart from a stream of trades.
+/// A line is extended as long as the price moves in its direction.
+/// Once the price moves at least the reversal amount against the line, starting from its most extreme price,
+/// the line is finished and a line in the opposite direction starts at the extreme price.
+/// The first line starts once the price moved the reversal amount away from the first trade.
+/// Trades with a price that is not finite are ignored.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct KagiAggregator {
+    reversal: KagiReversal,
+    // The first trade, until the first line starts
+    first: Option<(f64, i64)>,
+    line: Option<KagiLine>,
+    // The start of the previous line, which is the shoulder for a rising line and the waist for a falling one
+    previous_start: Option<f64>,
+}
+
+impl KagiAggregator {
+    /// Create a new instance
+    ///
+    /// # Arguments:
+    /// `reversal`: The positive and finite price movement against the current line which reverses it
+    ///
+    pub fn new(reversal: KagiReversal) -> Result<Self> {
+        let amount = match reversal {
+            KagiReversal::Absolute(amount) | KagiReversal::Relative(amount) => amount,
+        };
+        if !amount.is_finite() || amount <= 0.0 {
+            return Err(Error::InvalidParam);
+        }
+
+        Ok(Self {
+            reversal,
+            first: None,
+            line: None,
+            previous_start: None,
+        })
+    }
+
+    /// Adds a new trade to the chart
+    ///
+    /// # Arguments:
+    /// trade: the trade information to add to the aggregation process
+    ///
+    /// # Returns:
+    /// Some line only when it has been finished by a reversal,
+    /// otherwise it returns None
+    pub fn update<T: TakerTrade>(&mut self, trade: &T) -> Option<KagiLine> {
+        let price = trade.price();
+        let ts = trade.timestamp();
+        use BrickDirection::*;
+        // A price that is not finite would neither extend nor reverse the line correctly
+        if !price.is_finite() {
+            return None;
+        }
+
+        let Some(line) = self.line.as_mut() else {
+            let (start, start_timestamp) = *self.first.get_or_insert((price, ts));
+            let direction = if price - start >= self.reversal.amount(start) {
+                Up
+            } else if start - price >= self.reversal.amount(start) {
+                Down
+            } else {
+                return None;
+            };
+            self.line = Some(KagiLine {
+                direction,
+                start,
+                end: price,
+                yang: direction == Up,
+                start_timestamp,
+                end_timestamp: ts,
+            });
+            return None;
+        };
+
+        let extends = match line.direction {
+            Up => price > line.end,
+            Down => price < line.end,
+        };
+        if extends {
+            line.end = price;
+            line.end_timestamp = ts;
+            Self::update_thickness(line, self.previous_start);
+            return None;
+        }
+        if (line.end - price).abs() < self.reversal.amount(line.end) {
+            return None;
+        }
+
+        let finished = line.clone();
+        let direction = match line.direction {
+            Up => Down,
+            Down => Up,
+        };
+        *line = KagiLine {
+            direction,
+            start: finished.end,
+            end: price,
+            yang: finished.yang,
+            start_timestamp: ts,
+            end_timestamp: ts,
+        };
+        self.previous_start = Some(finished.start);
+        Self::update_thickness(line, self.previous_start);
+
+        Some(finished)
+    }
+
+    /// The line that is currently being extended, or None if the first line has not started yet
+    pub fn current_line(&self) -> Option<&KagiLine> {
+        self.line.as_ref()
+    }
+
+    fn update_thickness(line: &mut KagiLine, previous_start: Option<f64>) {
+        let Some(previous_start) = previous_start else {
+            return;
+        };
+        match line.direction {
+            BrickDirection::Up if line.end > previous_start => line.yang = true,
+            BrickDirection::Down if line.end < previous_start => line.yang = false,
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Trade;
+
+    fn trade(timestamp: i64, price: f64) -> Trade {
+        Trade {
+            timestamp,
+            price,
+            size: 1.0,
+        }
+    }
+
+    #[test]
+    fn kagi_aggregator() {
+        use BrickDirection::*;
+
+        assert!(KagiAggregator::new(KagiReversal::Absolute(0.0)).is_err());
+
+        let mut a = KagiAggregator::new(KagiReversal::Absolute(2.0)).unwrap();
+        let prices = [100.0, 101.0, 98.0, 97.0, 99.0, 102.0, 100.0, 96.0];
+        let lines: Vec<_> = prices
+            .iter()
+            .enumerate()
+            .filter_map(|(i, price)| a.update(&trade(i as i64, *price)))
+            .collect();
+
+        assert_eq!(
+            lines,
+            vec![
+                KagiLine {
+                    direction: Down,
+                    start: 100.0,
+                    end: 97.0,
+                    yang: false,
+                    start_timestamp: 0,
+                    end_timestamp: 3,
+                },
+                // Rising above the shoulder at 100 turns the line thick
+                KagiLine {
+                    direction: Up,
+                    start: 97.0,
+                    end: 102.0,
+                    yang: true,
+                    start_timestamp: 4,
+                    end_timestamp: 5,
+                },
+            ]
+        );
+
+        // Falling below the waist at 97 turns the line thin
+        let line = a.current_line().unwrap();
+        assert_eq!((line.direction, line.start, line.end), (Down, 102.0, 96.0));
+        assert!(!line.yang);
+    }
+
+    #[test]
+    fn kagi_aggregator_relative() {
+        let mut a = KagiAggregator::new(KagiReversal::Relative(0.01)).unwrap();
+        assert!(a.update(&trade(0, 100.0)).is_none());
+        assert!(a.update(&trade(1, 101.0)).is_none());
+        assert_eq!(a.current_line().unwrap().direction, BrickDirection::Up);
+        assert!(a.update(&trade(2, 120.0)).is_none());
+        // 1% of 120 is 1.2
+        assert!(a.update(&trade(3, 118.9)).is_none());
+        let line = a.update(&trade(4, 118.8)).unwrap();
+        assert_eq!((line.start, line.end), (100.0, 120.0));
+    }
+
+    #[test]
+    fn kagi_aggregator_non_finite() {
+        for amount in [f64::NAN, f64::INFINITY] {
+            assert!(KagiAggregator::new(KagiReversal::Absolute(amount)).is_err());
+            assert!(KagiAggregator::new(KagiReversal::Relative(amount)).is_err());
+        }
+
+        let mut a = KagiAggregator::new(KagiReversal::Absolute(2.0)).unwrap();
+        // Not even the first trade may be a price that is not finite
+        assert!(a.update(&trade(0, f64::NAN)).is_none());
+        assert!(a.update(&trade(1, 100.0)).is_none());
+        assert!(a.update(&trade(2, 103.0)).is_none());
+        for (i, price) in [f64::NAN, f64::INFINITY, f64::NEG_INFINITY]
+            .iter()
+            .enumerate()
+        {
+            assert!(a.update(&trade(3 + i as i64, *price)).is_none());
+        }
+        let line = a.current_line().unwrap();
+        assert_eq!((line.start, line.end), (100.0, 103.0));
+        assert_eq!(line.end_timestamp, 2);
+    }
+}
@@ -0,0 +1,303 @@
+use std::{collections::HashMap, fmt, hash::Hash};
+
+use crate::{AggregationRule, Aggregator, GenericAggregator, ModularCandle, TakerTrade};
+
+/// A finished candle of a `KeyedAggregator`,
+/// tagged with the key of the aggregator that produced it, e.g. the symbol.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct KeyedCandle<K, C> {
+    /// The key of the trades the candle consists of
+    pub key: K,
+
+    /// The finished candle
+    pub candle: C,
+}
+
+#[derive(Debug)]
+struct Entry<C, R, T> {
+    aggregator: GenericAggregator<C, R, T>,
+    // The timestamp of the most recent trade of this key
+    last_timestamp: i64,
+}
+
+/// Routes each trade to a separate `GenericAggregator` per key, e.g. per symbol,
+/// using a user supplied key extractor.
+/// The aggregator of a key is created from a rule factory, the first time the key is seen.
+pub struct KeyedAggregator<K, C, R, T> {
+    aggregators: HashMap<K, Entry<C, R, T>>,
+    key_fn: Box<dyn Fn(&T) -> K + Send>,
+    rule_factory: Box<dyn FnMut(&K) -> R + Send>,
+    include_trade_that_triggered_rule: bool,
+}
+
+impl<K, C, R, T> KeyedAggregator<K, C, R, T>
+where
+    K: Clone + Eq + Hash,
+    C: ModularCandle<T>,
+    R: AggregationRule<C, T>,
+    T: TakerTrade,
+{
+    /// Create a new instance without any keys
+    ///
+    /// # Arguments:
+    /// `key_fn`: Extracts the key from a trade, e.g. its symbol.
+    /// `rule_factory`: Creates the `AggregationRule` for a key that has not been seen before.
+    /// `include_trade_that_triggered_rule`: Passed to each `GenericAggregator::new`.
+    ///
+    pub fn new(
+        key_fn: impl Fn(&T) -> K + Send + 'static,
+        rule_factory: impl FnMut(&K) -> R + Send + 'static,
+        include_trade_that_triggered_rule: bool,
+    ) -> Self {
+        Self {
+            aggregators: HashMap::new(),
+            key_fn: Box::new(key_fn),
+            rule_factory: Box::new(rule_factory),
+            include_trade_that_triggered_rule,
+        }
+    }
+
+    /// Adds a new trade to the aggregator of its key
+    ///
+    /// # Arguments:
+    /// trade: the trade information to add to the aggregation process
+    ///
+    /// # Returns:
+    /// Some output only when a new candle has been created for the key of the trade,
+    /// otherwise it returns None
+    pub fn update(&mut self, trade: &T) -> Option<KeyedCandle<K, C>> {
+        let key = (self.key_fn)(trade);
+        let entry = match self.aggregators.get_mut(&key) {
+            Some(entry) => entry,
+            None => {
+                let rule = (self.rule_factory)(&key);
+                self.aggregators.entry(key.clone()).or_insert(Entry {
+                    aggregator: GenericAggregator::new(
+                        rule,
+                        self.include_trade_that_triggered_rule,
+                    ),
+                    last_timestamp: trade.timestamp(),
+                })
+            }
+        };
+        entry.last_timestamp = trade.timestamp();
+
+        let candle = entry.aggregator.update(trade)?;
+        Some(KeyedCandle { key, candle })
+    }
+
+    /// Get a reference to the aggregator of a key, if the key has been seen
+    pub fn aggregator(&self, key: &K) -> Option<&GenericAggregator<C, R, T>> {
+        self.aggregators.get(key).map(|e| &e.aggregator)
+    }
+
+    /// Get a reference to the unfinished candle of a key, if the key has been seen
+    pub fn unfinished_candle(&self, key: &K) -> Option<&C> {
+        self.aggregator(key).map(|a| a.unfinished_candle())
+    }
+
+    /// An iterator over all keys that currently have an aggregator
+    pub fn keys(&self) -> impl Iterator<Item = &K> {
+        self.aggregators.keys()
+    }
+
+    /// The number of keys that currently have an aggregator
+    pub fn len(&self) -> usize {
+        self.aggregators.len()
+    }
+
+    /// Returns true if no key currently has an aggregator
+    pub fn is_empty(&self) -> bool {
+        self.aggregators.is_empty()
+    }
+
+    /// Finishes the current candles of all keys regardless of their `AggregationRule`,
+    /// see `Aggregator::flush`. The keys are kept.
+    ///
+    /// # Returns:
+    /// The candles that contain at least one trade, in no particular order
+    pub fn flush_all(&mut self) -> Vec<KeyedCandle<K, C>> {
+        self.aggregators
+            .iter_mut()
+            .filter_map(|(key, e)| {
+                e.aggregator.flush().map(|candle| KeyedCandle {
+                    key: key.clone(),
+                    candle,
+                })
+            })
+            .collect()
+    }
+
+    /// Removes the aggregators of all keys without a trade for longer than `max_idle`,
+    /// e.g. delisted or expired instruments.
+    /// A key that is seen again afterwards starts with a new aggregator from the rule factory.
+    ///
+    /// # Arguments:
+    /// `now`: The current time, in the unit of the trade timestamps
+    /// `max_idle`: The duration without trades after which a key is removed, in the unit of the trade timestamps
+    ///
+    /// # Returns:
+    /// The flushed unfinished candles of the removed keys, in no particular order
+    pub fn evict_idle(&mut self, now: i64, max_idle: i64) -> Vec<KeyedCandle<K, C>> {
+        let idle: Vec<K> = self
+            .aggregators
+            .iter()
+            .filter(|(_, e)| now - e.last_timestamp > max_idle)
+            .map(|(key, _)| key.clone())
+            .collect();
+
+        idle.into_iter()
+            .filter_map(|key| {
+                let mut e = self.aggregators.remove(&key)?;
+                e.aggregator
+                    .flush()
+                    .map(|candle| KeyedCandle { key, candle })
+            })
+            .collect()
+    }
+}
+
+impl<K, C, R, T> fmt::Debug for KeyedAggregator<K, C, R, T>
+where
+    K: fmt::Debug,
+    C: fmt::Debug,
+    R: fmt::Debug,
+    T: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // The key extractor and the rule factory are closures, which can't be printed
+        f.debug_struct("KeyedAggregator")
+            .field("aggregators", &self.aggregators)
+            .field(
+                "include_trade_that_triggered_rule",
+                &self.include_trade_that_triggered_rule,
+            )
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::marker::PhantomData;
+
+    use trade_aggregation_derive::Candle;
+
+    use super::*;
+    use crate::{
+        candle_components::{tests::TRADES, Close, Open},
+        TickRule, Trade,
+    };
+
+    #[derive(Debug, Clone, Copy)]
+    struct SymbolTrade {
+        symbol: &'static str,
+        trade: Trade,
+    }
+
+    impl TakerTrade for SymbolTrade {
+        fn timestamp(&self) -> i64 {
+            self.trade.timestamp
+        }
+
+        fn price(&self) -> f64 {
+            self.trade.price
+        }
+
+        fn size(&self) -> f64 {
+            self.trade.size
+        }
+    }
+
+    #[derive(Debug, Default, Clone, Candle)]
+    struct MyCandle {
+        open: Open,
+        close: Close,
+        input: PhantomData<SymbolTrade>,
+    }
+
+    fn trades() -> Vec<SymbolTrade> {
+        // Every third trade belongs to ETH, the rest to BTC
+        TRADES
+            .iter()
+            .enumerate()
+            .map(|(i, t)| SymbolTrade {
+                symbol: if i % 3 == 2 { "ETH" } else { "BTC" },
+                trade: *t,
+            })
+            .collect()
+    }
+
+    fn aggregator() -> KeyedAggregator<&'static str, MyCandle, TickRule, SymbolTrade> {
+        KeyedAggregator::new(
+            |t: &SymbolTrade| t.symbol,
+            |symbol: &&str| TickRule::new(if *symbol == "BTC" { 3 } else { 2 }),
+            false,
+        )
+    }
+
+    #[test]
+    fn keyed_aggregator() {
+        let mut a = aggregator();
+        assert!(a.is_empty());
+
+        let candles: Vec<_> = trades().iter().filter_map(|t| a.update(t)).collect();
+        assert_eq!(a.len(), 2);
+        assert_eq!(
+            candles.iter().map(|c| c.key).collect::<Vec<_>>(),
+            vec!["BTC", "ETH", "BTC"]
+        );
+        // BTC trades: 0, 1, 3, 4, 6, 7, 9
+        assert_eq!(candles[0].candle.open(), TRADES[0].price);
+        assert_eq!(candles[0].candle.close(), TRADES[1].price);
+        // ETH trades: 2, 5, 8
+        assert_eq!(candles[1].candle.open(), TRADES[2].price);
+        assert_eq!(candles[2].candle.open(), TRADES[3].price);
+        assert_eq!(candles[2].candle.close(), TRADES[6].price);
+        assert_eq!(a.unfinished_candle(&"ETH").unwrap().open(), TRADES[5].price);
+        assert!(a.unfinished_candle(&"SOL").is_none());
+
+        let mut flushed = a.flush_all();
+        flushed.sort_by_key(|c| c.key);
+        assert_eq!(flushed.len(), 2);
+        assert_eq!(flushed[0].key, "BTC");
+        assert_eq!(flushed[0].candle.open(), TRADES[7].price);
+        assert_eq!(flushed[0].candle.close(), TRADES[9].price);
+        assert_eq!(flushed[1].key, "ETH");
+        assert_eq!(flushed[1].candle.close(), TRADES[8].price);
+        assert!(a.flush_all().is_empty());
+        assert_eq!(a.len(), 2);
+    }
+
+    #[test]
+    fn keyed_aggregator_debug() {
+        let mut a = aggregator();
+        a.update(&trades()[0]);
+        let debug = format!("{a:?}");
+        assert!(debug.starts_with("KeyedAggregator { aggregators: {\"BTC\": Entry {"));
+        // The closures are left out
+        assert!(debug.ends_with("include_trade_that_triggered_rule: false, .. }"));
+    }
+
+    #[test]
+    fn keyed_aggregator_evict_idle() {
+        let mut a = aggregator();
+        let trades = trades();
+        for t in trades.iter().take(9) {
+            a.update(t);
+        }
+
+        // The last BTC trade is 10 seconds older than the last ETH trade
+        let now = TRADES[8].timestamp + 5_000;
+        assert!(a.evict_idle(now, 15_000).is_empty());
+        let evicted = a.evict_idle(now, 10_000);
+        assert_eq!(evicted.len(), 1);
+        assert_eq!(evicted[0].key, "BTC");
+        assert_eq!(evicted[0].candle.open(), TRADES[7].price);
+        assert_eq!(a.keys().collect::<Vec<_>>(), vec![&"ETH"]);
+
+        // A new aggregator is created once the key is seen again
+        assert!(a.update(&trades[9]).is_none());
+        assert_eq!(a.unfinished_candle(&"BTC").unwrap().open(), TRADES[9].price);
+    }
+}
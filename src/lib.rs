@@ -2,25 +2,51 @@
 
 //! This crate is used for aggregating raw trade data into candles using various methods
 
+// Allows the `Candle` derive to refer to this crate by name from within the crate itself.
+extern crate self as trade_aggregation;
+
 #[cfg(test)]
 mod plot;
 
+mod aggregation_event;
 mod aggregation_rules;
 mod aggregator;
 pub mod candle_components;
 mod constants;
 mod errors;
+mod ewma;
+mod heikin_ashi;
+mod kagi_aggregator;
+mod keyed_aggregator;
+mod line_break_aggregator;
+#[doc(hidden)]
+pub mod merge_probe;
 mod modular_candle_trait;
+mod multi_timeframe_aggregator;
+#[doc(hidden)]
+pub mod ohlc_markers;
+mod ohlc_trait;
+mod point_and_figure_aggregator;
+mod renko_aggregator;
 mod types;
 mod utils;
 mod welford_online;
 
+pub use aggregation_event::AggregationEvent;
 pub use aggregation_rules::*;
 pub use aggregator::*;
-pub use candle_components::{CandleComponent, CandleComponentUpdate};
+pub use candle_components::{CandleComponent, CandleComponentUpdate, MergeableComponent};
 pub use constants::*;
 pub use errors::*;
+pub use heikin_ashi::{HeikinAshi, HeikinAshiCandle};
+pub use kagi_aggregator::{KagiAggregator, KagiLine, KagiReversal};
+pub use keyed_aggregator::{KeyedAggregator, KeyedCandle};
+pub use line_break_aggregator::{LineBreakAggregator, LineBreakLine};
 pub use modular_candle_trait::ModularCandle;
+pub use multi_timeframe_aggregator::{MultiTimeframeAggregator, TimeframeCandle};
+pub use ohlc_trait::Ohlc;
+pub use point_and_figure_aggregator::{PointAndFigureAggregator, PointAndFigureColumn};
+pub use renko_aggregator::{BrickDirection, BrickSize, RenkoAggregator, RenkoBrick};
 pub use trade_aggregation_derive::Candle;
 pub use types::*;
 pub use utils::*;
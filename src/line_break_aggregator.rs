@@ -0,0 +1,325 @@
+use std::{collections::VecDeque, marker::PhantomData};
+
+use crate::{BrickDirection, Error, ModularCandle, Result, TakerTrade};
+
+/// A line of a line break chart
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LineBreakLine<C> {
+    /// The direction of the line
+    pub direction: BrickDirection,
+
+    /// The price at which the line opens, the high or low of the previous line
+    pub open: f64,
+
+    /// The price of the trade that created the line
+    pub close: f64,
+
+    /// The candle of the trades since the previous line, including the trade that created this line
+    pub candle: C,
+}
+
+impl<C> LineBreakLine<C> {
+    /// The higher price of the open and close
+    pub fn high(&self) -> f64 {
+        self.open.max(self.close)
+    }
+
+    /// The lower price of the open and close
+    pub fn low(&self) -> f64 {
+        self.open.min(self.close)
+    }
+}
+
+/// Creates the lines of an N line break chart, e.g. the classic three line break chart.
+/// A rising line is created once the price exceeds the highest high of the previous N lines,
+/// and a falling line once the price falls below the lowest low of the previous N lines.
+/// So the trend continues with a move beyond the close of the last line,
+/// while a reversal requires a move beyond the extreme of the last N lines.
+/// A new line opens at the high or low of the previous line, in its direction.
+/// Until the first line, the price of the first trade acts as the previous line.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LineBreakAggregator<C, T> {
+    n_lines: usize,
+    // The open and close of the most recent lines, at most `n_lines`, ordered from the oldest
+    lines: VecDeque<(f64, f64)>,
+    // The price of the first trade, until the first line has been created
+    first_price: Option<f64>,
+    candle: C,
+    _trade_type: PhantomData<T>,
+}
+
+impl<C, T> LineBreakAggregator<C, T>
+where
+    C: ModularCandle<T>,
+    T: TakerTrade,
+{
+    /// Create a new instance
+    ///
+    /// # Arguments:
+    /// `n_lines`: The number of previous lines the price has to break to reverse the trend, e.g. 3
+    ///
+    pub fn new(n_lines: usize) -> Result<Self> {
+        if n_lines == 0 {
+            return Err(Error::InvalidParam);
+        }
+
+        Ok(Self {
+            n_lines,
+            lines: VecDeque::with_capacity(n_lines),
+            first_price: None,
+            candle: Default::default(),
+            _trade_type: PhantomData,
+        })
+    }
+
+    /// Adds a new trade to the chart
+    ///
+    /// # Arguments:
+    /// trade: the trade information to add to the aggregation process
+    ///
+    /// # Returns:
+    /// Some line only when the trade created a new line,
+    /// otherwise it returns None
+    pub fn update(&mut self, trade: &T) -> Option<LineBreakLine<C>> {
+        let price = trade.price();
+        // A price that is not finite can't be compared with the previous lines
+        if !price.is_finite() {
+            return None;
+        }
+        self.candle.update(trade);
+
+        let (high, low) = if self.lines.is_empty() {
+            let first = *self.first_price.get_or_insert(price);
+            (first, first)
+        } else {
+            self.lines
+                .iter()
+                .fold((f64::MIN, f64::MAX), |(h, l), (o, c)| {
+                    (h.max(o.max(*c)), l.min(o.min(*c)))
+                })
+        };
+
+        let (last_open, last_close) = self.lines.back().copied().unwrap_or((high, low));
+        let (direction, open) = if price > high {
+            (BrickDirection::Up, last_open.max(last_close))
+        } else if price < low {
+            (BrickDirection::Down, last_open.min(last_close))
+        } else {
+            return None;
+        };
+
+        if self.lines.len() == self.n_lines {
+            self.lines.pop_front();
+        }
+        self.lines.push_back((open, price));
+
+        let line = LineBreakLine {
+            direction,
+            open,
+            close: price,
+            candle: self.candle.clone(),
+        };
+        self.candle.reset();
+
+        Some(line)
+    }
+
+    /// Get a reference to the candle of the trades since the last line
+    pub fn unfinished_candle(&self) -> &C {
+        &self.candle
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        candle_components::{Close, NumTrades, Open},
+        load_trades_from_csv, Candle, Trade,
+    };
+
+    #[derive(Debug, Default, Clone, Candle)]
+    struct LineCandle {
+        open: Open,
+        close: Close,
+        num_trades: NumTrades<u32>,
+    }
+
+    /// A straightforward implementation keeping all lines,
+    /// returning the direction, open and close of each line
+    fn reference(prices: &[f64], n: usize) -> Vec<(BrickDirection, f64, f64)> {
+        let mut lines: Vec<LineBreakLine<()>> = vec![];
+        for price in prices {
+            let window = &lines[lines.len().saturating_sub(n)..];
+            let (high, low, prev_high, prev_low) = match lines.last() {
+                None => (prices[0], prices[0], prices[0], prices[0]),
+                Some(last) => (
+                    window.iter().map(|l| l.high()).fold(f64::MIN, f64::max),
+                    window.iter().map(|l| l.low()).fold(f64::MAX, f64::min),
+                    last.high(),
+                    last.low(),
+                ),
+            };
+            let (direction, open) = if *price > high {
+                (BrickDirection::Up, prev_high)
+            } else if *price < low {
+                (BrickDirection::Down, prev_low)
+            } else {
+                continue;
+            };
+            lines.push(LineBreakLine {
+                direction,
+                open,
+                close: *price,
+                candle: (),
+            });
+        }
+
+        lines
+            .iter()
+            .map(|l| (l.direction, l.open, l.close))
+            .collect()
+    }
+
+    #[test]
+    fn line_break_aggregator() {
+        use BrickDirection::*;
+
+        assert!(LineBreakAggregator::<LineCandle, Trade>::new(0).is_err());
+
+        let mut a = LineBreakAggregator::<LineCandle, Trade>::new(3).unwrap();
+        let prices = [10.0, 11.0, 12.0, 13.0, 11.5, 10.5, 9.0, 9.5, 13.5];
+        let lines: Vec<_> = prices
+            .iter()
+            .filter_map(|price| {
+                a.update(&Trade {
+                    timestamp: 0,
+                    price: *price,
+                    size: 1.0,
+                })
+            })
+            .collect();
+        assert_eq!(
+            lines
+                .iter()
+                .map(|l| (l.direction, l.open, l.close))
+                .collect::<Vec<_>>(),
+            vec![
+                (Up, 10.0, 11.0),
+                (Up, 11.0, 12.0),
+                (Up, 12.0, 13.0),
+                // Breaking below the low of the last three lines reverses the trend
+                (Down, 12.0, 9.0),
+                // Breaking above the high of the last three lines reverses it again
+                (Up, 12.0, 13.5),
+            ]
+        );
+        assert_eq!(lines[3].candle.num_trades(), 3);
+        assert_eq!(lines[3].candle.open(), 11.5);
+        assert_eq!(lines[4].candle.num_trades(), 2);
+    }
+
+    #[test]
+    fn line_break_aggregator_hand_computed() {
+        use BrickDirection::*;
+
+        let prices = [10.0, 11.0, 10.5, 12.0, 11.5, 9.0, 11.5, 12.5, 8.0];
+        let expected = [
+            (
+                1,
+                vec![
+                    (Up, 10.0, 11.0),
+                    (Up, 11.0, 12.0),
+                    (Down, 11.0, 9.0),
+                    // Only the last line has to be broken
+                    (Up, 11.0, 11.5),
+                    (Up, 11.5, 12.5),
+                    (Down, 11.5, 8.0),
+                ],
+            ),
+            (
+                2,
+                vec![
+                    (Up, 10.0, 11.0),
+                    (Up, 11.0, 12.0),
+                    (Down, 11.0, 9.0),
+                    // 11.5 stays below the high of 12 of the last two lines
+                    (Up, 11.0, 12.5),
+                    (Down, 11.0, 8.0),
+                ],
+            ),
+        ];
+        for (n, expected) in expected {
+            let mut a = LineBreakAggregator::<LineCandle, Trade>::new(n).unwrap();
+            let lines: Vec<_> = prices
+                .iter()
+                .filter_map(|price| {
+                    a.update(&Trade {
+                        timestamp: 0,
+                        price: *price,
+                        size: 1.0,
+                    })
+                })
+                .map(|l| (l.direction, l.open, l.close))
+                .collect();
+            assert_eq!(lines, expected, "n = {n}");
+            assert_eq!(reference(&prices, n), expected, "n = {n}");
+        }
+    }
+
+    #[test]
+    fn line_break_aggregator_non_finite() {
+        let mut a = LineBreakAggregator::<LineCandle, Trade>::new(3).unwrap();
+        let mut update = |price| {
+            a.update(&Trade {
+                timestamp: 0,
+                price,
+                size: 1.0,
+            })
+        };
+        // Not even the first trade may be a price that is not finite
+        assert!(update(f64::NAN).is_none());
+        assert!(update(10.0).is_none());
+        for price in [f64::NAN, f64::INFINITY, f64::NEG_INFINITY] {
+            assert!(update(price).is_none());
+        }
+        let line = update(11.0).unwrap();
+        assert_eq!((line.open, line.close), (10.0, 11.0));
+        // The skipped trades are not part of the candle of the line
+        assert_eq!(line.candle.num_trades(), 2);
+        for price in [f64::NAN, 10.5] {
+            assert!(update(price).is_none());
+        }
+        assert_eq!(a.unfinished_candle().num_trades(), 1);
+    }
+
+    #[test]
+    fn line_break_aggregator_matches_reference() {
+        let trades = load_trades_from_csv("data/Bitstamp_BTCEUR_1M.csv").unwrap();
+        let prices: Vec<f64> = trades.iter().map(|t| t.price).collect();
+
+        for n in [1, 2, 3, 5] {
+            let mut a = LineBreakAggregator::<LineCandle, Trade>::new(n).unwrap();
+            let lines: Vec<_> = trades.iter().filter_map(|t| a.update(t)).collect();
+            let expected = reference(&prices, n);
+            assert!(!expected.is_empty(), "n = {n}");
+            assert_eq!(
+                lines
+                    .iter()
+                    .map(|l| (l.direction, l.open, l.close))
+                    .collect::<Vec<_>>(),
+                expected,
+                "n = {n}"
+            );
+
+            // Every trade is part of exactly one line or the unfinished candle
+            let num_trades: u32 = lines.iter().map(|l| l.candle.num_trades()).sum();
+            assert_eq!(
+                num_trades + a.unfinished_candle().num_trades(),
+                trades.len() as u32
+            );
+        }
+    }
+}
@@ -0,0 +1,55 @@
+//! Allows the `Candle` derive to find out whether a component is a `MergeableComponent`,
+//! which a derive macro can't resolve by itself from the name of a type.
+//! The method of `Mergeable` takes the probe itself and is preferred, if the component can be merged.
+//! Otherwise the method of `NotMergeable` is found by auto referencing the probe.
+
+use std::marker::PhantomData;
+
+use crate::MergeableComponent;
+
+/// Probes whether the component `C` can be merged, see the module docs
+pub struct MergeProbe<C>(PhantomData<C>);
+
+impl<C> MergeProbe<C> {
+    /// Create a new probe for the component `C`
+    #[allow(clippy::new_without_default)]
+    pub const fn new() -> Self {
+        Self(PhantomData)
+    }
+}
+
+/// Merges components that are `MergeableComponent`s
+pub trait Mergeable<C> {
+    /// Always true
+    fn is_mergeable(&self) -> bool;
+
+    /// Merges the state of a subsequent candle into `component`
+    fn merge(&self, component: &mut C, other: &C);
+}
+
+impl<C: MergeableComponent> Mergeable<C> for MergeProbe<C> {
+    #[inline(always)]
+    fn is_mergeable(&self) -> bool {
+        true
+    }
+
+    #[inline(always)]
+    fn merge(&self, component: &mut C, other: &C) {
+        component.merge(other);
+    }
+}
+
+/// The fallback for components that are not `MergeableComponent`s
+pub trait NotMergeable<C> {
+    /// Always false
+    #[inline(always)]
+    fn is_mergeable(&self) -> bool {
+        false
+    }
+
+    /// Does nothing, as the component can't be merged
+    #[inline(always)]
+    fn merge(&self, _component: &mut C, _other: &C) {}
+}
+
+impl<C> NotMergeable<C> for &MergeProbe<C> {}
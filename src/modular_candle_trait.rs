@@ -9,4 +9,35 @@ pub trait ModularCandle<T: TakerTrade>: Clone + Default {
 
     /// Resets the state of the candle
     fn reset(&mut self);
+
+    /// Updates the just reset candle with the trade that also finished the previous candle,
+    /// see `CandleComponentUpdate::carry_over`.
+    /// The default implementation updates the candle like with any other trade,
+    /// the derived implementation calls `carry_over` of every component instead.
+    fn carry_over(&mut self, trade: &T) {
+        self.update(trade);
+    }
+
+    /// Merges a subsequent candle into this one, as if this candle had been updated with the trades of both,
+    /// if every component is a `MergeableComponent`.
+    /// The default implementation can't merge, the derived implementation merges
+    /// by merging each of the components.
+    ///
+    /// # Returns:
+    /// Whether the candle has been merged, otherwise it is left unchanged
+    fn try_merge(&mut self, _other: &Self) -> bool {
+        false
+    }
+
+    /// Sets the just reset candle to a period without any trades, see `CandleComponentUpdate::fill_gap`.
+    /// The default implementation updates the candle with both zero sized trades,
+    /// the derived implementation calls `fill_gap` of every component instead.
+    ///
+    /// # Arguments:
+    /// `open`: A zero sized trade at the open timestamp and price of the period
+    /// `close`: A zero sized trade at the close timestamp and price of the period
+    fn fill_gap(&mut self, open: &T, close: &T) {
+        self.update(open);
+        self.update(close);
+    }
 }
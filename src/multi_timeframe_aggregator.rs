@@ -0,0 +1,363 @@
+use std::marker::PhantomData;
+
+use crate::{Error, MillisecondPeriod, ModularCandle, Result, TakerTrade, TimestampResolution};
+
+/// A finished candle of a `MultiTimeframeAggregator`,
+/// tagged with the timeframe it belongs to.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TimeframeCandle<C> {
+    /// The period of the timeframe, e.g. `M5`
+    pub period: MillisecondPeriod,
+
+    /// The finished candle
+    pub candle: C,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct Timeframe<C> {
+    period: MillisecondPeriod,
+    // The period in the unit of the incoming trades
+    period_in_units_from_trade: i64,
+    candle: C,
+    // The number of trades in the current candle
+    num_trades: usize,
+}
+
+/// Aggregates a single trade stream into candles of multiple aligned timeframes at once,
+/// e.g. 1 minute, 5 minute and 1 hour candles.
+/// Just like the `AlignedTimeRule`, all periods are aligned to a time of zero,
+/// so the candles of a higher timeframe always cover the same trades as the lower timeframe candles within it.
+///
+/// Every period has to be a multiple of the smallest one,
+/// which allows checking the period boundary only once per trade for the smallest timeframe,
+/// while the higher timeframes are only checked once a smallest timeframe candle is finished.
+///
+/// If the candles can be merged (see `ModularCandle::try_merge`),
+/// e.g. a derived candle of only `MergeableComponent`s,
+/// each trade only updates the candle of the smallest timeframe
+/// and the higher timeframes are rolled up from its finished candles.
+/// Otherwise, e.g. for candles containing the `Vpin` or components of your own that can't be merged,
+/// each trade is added to the candle of every timeframe, at the cost of one candle update per timeframe and trade.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MultiTimeframeAggregator<C, T> {
+    // Sorted by ascending period
+    timeframes: Vec<Timeframe<C>>,
+    // The start of the current period of the smallest timeframe, in the unit of the incoming trades,
+    // or None before the first trade
+    reference_timestamp: Option<i64>,
+    // Whether the higher timeframes are rolled up from the finished candles of the smallest timeframe
+    merge_candles: bool,
+    _trade_type: PhantomData<T>,
+}
+
+impl<C, T> MultiTimeframeAggregator<C, T>
+where
+    C: ModularCandle<T>,
+    T: TakerTrade,
+{
+    /// Create a new instance with an empty candle for each timeframe.
+    ///
+    /// # Arguments:
+    /// `periods`: The candle periods, e.g. `&[M1, M5, M15, H1, H4]`.
+    ///     Each period must be a multiple of the smallest one. Duplicates are ignored.
+    ///     For `TimestampResolution::Second`, each period must be a whole number of seconds.
+    /// `trade_timestamp_resolution`: The resolution each Trade timestamp will have
+    ///
+    pub fn new(
+        periods: &[MillisecondPeriod],
+        trade_timestamp_resolution: TimestampResolution,
+    ) -> Result<Self> {
+        let mut periods = periods.to_vec();
+        periods.sort();
+        periods.dedup();
+        let Some(smallest) = periods.first() else {
+            return Err(Error::InvalidParam);
+        };
+        if periods.iter().any(|p| p.get() % smallest.get() != 0) {
+            return Err(Error::InvalidParam);
+        }
+
+        Ok(Self {
+            timeframes: periods
+                .into_iter()
+                .map(|period| {
+                    Ok(Timeframe {
+                        period,
+                        period_in_units_from_trade: trade_timestamp_resolution
+                            .period_in_units(period)?,
+                        candle: Default::default(),
+                        num_trades: 0,
+                    })
+                })
+                .collect::<Result<_>>()?,
+            reference_timestamp: None,
+            merge_candles: C::default().try_merge(&C::default()),
+            _trade_type: PhantomData,
+        })
+    }
+
+    /// Adds a new trade to the candles of all timeframes
+    ///
+    /// # Arguments:
+    /// trade: the trade information to add to the aggregation process
+    ///
+    /// # Returns:
+    /// The candles finished by this trade, ordered by ascending period.
+    /// Empty if no candle has been finished.
+    pub fn update(&mut self, trade: &T) -> Vec<TimeframeCandle<C>> {
+        let base_period = self.timeframes[0].period_in_units_from_trade;
+        let ts = trade.timestamp();
+        let period_start = ts - ts.rem_euclid(base_period);
+        let reference_timestamp = *self.reference_timestamp.get_or_insert(period_start);
+
+        let mut out = vec![];
+        if ts - reference_timestamp >= base_period {
+            self.merge_smallest_candle();
+            // As all periods are multiples of the smallest one,
+            // a higher timeframe can only finish together with the smallest one.
+            for tf in self.timeframes.iter_mut() {
+                let p = tf.period_in_units_from_trade;
+                if ts.div_euclid(p) == reference_timestamp.div_euclid(p) {
+                    break;
+                }
+                if tf.num_trades > 0 {
+                    out.push(TimeframeCandle {
+                        period: tf.period,
+                        candle: tf.candle.clone(),
+                    });
+                }
+                tf.candle.reset();
+                tf.num_trades = 0;
+            }
+            self.reference_timestamp = Some(period_start);
+        }
+
+        // The higher timeframes are updated with every trade if their candles can't be merged
+        let num_updated = if self.merge_candles {
+            1
+        } else {
+            self.timeframes.len()
+        };
+        for tf in self.timeframes.iter_mut().take(num_updated) {
+            tf.candle.update(trade);
+            tf.num_trades += 1;
+        }
+
+        out
+    }
+
+    /// Rolls the candle of the smallest timeframe up into the higher timeframes, if the candles are merged
+    fn merge_smallest_candle(&mut self) {
+        let Some((smallest, higher)) = self.timeframes.split_first_mut() else {
+            return;
+        };
+        if !self.merge_candles || smallest.num_trades == 0 {
+            return;
+        }
+        for tf in higher.iter_mut() {
+            tf.candle.try_merge(&smallest.candle);
+            tf.num_trades += smallest.num_trades;
+        }
+    }
+
+    /// Get a reference to the unfinished candle of a timeframe,
+    /// or None if the aggregator does not contain the period.
+    /// If the candles are merged, the unfinished candle of a higher timeframe
+    /// only contains the finished candles of the smallest timeframe.
+    pub fn unfinished_candle(&self, period: MillisecondPeriod) -> Option<&C> {
+        self.timeframes
+            .iter()
+            .find(|tf| tf.period == period)
+            .map(|tf| &tf.candle)
+    }
+
+    /// Finishes the current candles of all timeframes regardless of their period,
+    /// e.g. to emit the last partial candles at the end of a batch of trades.
+    ///
+    /// # Returns:
+    /// The candles that contain at least one trade, ordered by ascending period.
+    pub fn flush(&mut self) -> Vec<TimeframeCandle<C>> {
+        self.merge_smallest_candle();
+        let mut out = vec![];
+        for tf in self.timeframes.iter_mut() {
+            if tf.num_trades > 0 {
+                out.push(TimeframeCandle {
+                    period: tf.period,
+                    candle: tf.candle.clone(),
+                });
+            }
+            tf.candle.reset();
+            tf.num_trades = 0;
+        }
+        self.reference_timestamp = None;
+
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use trade_aggregation_derive::Candle;
+
+    use super::*;
+    use crate::{
+        aggregate_all_trades,
+        candle_components::{tests::TRADES, NumTrades, Vpin},
+        plot::OhlcCandle,
+        AlignedTimeRule, GenericAggregator, Trade, M1, M5,
+    };
+
+    const S20: MillisecondPeriod = MillisecondPeriod::from_non_zero_secs(20);
+    const S40: MillisecondPeriod = MillisecondPeriod::from_non_zero_secs(40);
+
+    #[test]
+    fn multi_timeframe_aggregator_invalid_params() {
+        let new = |periods: &[MillisecondPeriod]| {
+            MultiTimeframeAggregator::<OhlcCandle, Trade>::new(
+                periods,
+                TimestampResolution::Millisecond,
+            )
+        };
+        assert!(new(&[]).is_err());
+        assert!(new(&[S20, MillisecondPeriod::from_non_zero_secs(30)]).is_err());
+        assert!(new(&[M5, M1, M1]).is_ok());
+        assert!(MultiTimeframeAggregator::<OhlcCandle, Trade>::new(
+            &[MillisecondPeriod::from_non_zero(1_500)],
+            TimestampResolution::Second
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn multi_timeframe_aggregator() {
+        let mut a = MultiTimeframeAggregator::<OhlcCandle, Trade>::new(
+            &[S40, S20],
+            TimestampResolution::Millisecond,
+        )
+        .unwrap();
+
+        let mut candles = vec![];
+        for t in TRADES.iter() {
+            candles.append(&mut a.update(t));
+        }
+        assert_eq!(
+            candles.iter().map(|c| c.period).collect::<Vec<_>>(),
+            vec![S20, S20, S40, S20, S20, S40]
+        );
+        // The first 40 second candle covers the first two 20 second candles
+        assert_eq!(candles[2].candle.open(), candles[0].candle.open());
+        assert_eq!(candles[2].candle.close(), candles[1].candle.close());
+        assert_eq!(
+            candles[2].candle.high(),
+            candles[0].candle.high().max(candles[1].candle.high())
+        );
+        assert!(a.merge_candles);
+        assert_eq!(a.unfinished_candle(S20).unwrap().open(), TRADES[8].price);
+        assert!(a.unfinished_candle(M1).is_none());
+
+        // The unfinished smallest candle is rolled up before flushing
+        let flushed = a.flush();
+        assert_eq!(flushed.len(), 2);
+        assert_eq!(flushed[1].period, S40);
+        assert_eq!(flushed[1].candle.open(), TRADES[8].price);
+        assert_eq!(flushed[1].candle.close(), TRADES[9].price);
+        assert!(a.flush().is_empty());
+    }
+
+    #[test]
+    fn multi_timeframe_aggregator_matches_aligned_time_rule() {
+        let mut a = MultiTimeframeAggregator::<OhlcCandle, Trade>::new(
+            &[S20, S40],
+            TimestampResolution::Millisecond,
+        )
+        .unwrap();
+        let candles: Vec<_> = TRADES.iter().flat_map(|t| a.update(t)).collect();
+
+        for period in [S20, S40] {
+            let mut aggregator = GenericAggregator::<OhlcCandle, _, Trade>::new(
+                AlignedTimeRule::new(period, TimestampResolution::Millisecond),
+                false,
+            );
+            let expected = aggregate_all_trades(&TRADES, &mut aggregator);
+            let got: Vec<_> = candles
+                .iter()
+                .filter(|c| c.period == period)
+                .map(|c| c.candle.clone())
+                .collect();
+            assert_eq!(got.len(), expected.len());
+            for (g, e) in got.iter().zip(expected.iter()) {
+                assert_eq!(g.open(), e.open());
+                assert_eq!(g.high(), e.high());
+                assert_eq!(g.low(), e.low());
+                assert_eq!(g.close(), e.close());
+            }
+        }
+    }
+
+    #[test]
+    fn multi_timeframe_aggregator_first_trade_at_zero() {
+        #[derive(Debug, Default, Clone, Candle)]
+        struct CountCandle {
+            num_trades: NumTrades<u32>,
+        }
+
+        let mut a = MultiTimeframeAggregator::<CountCandle, Trade>::new(
+            &[S20, S40],
+            TimestampResolution::Millisecond,
+        )
+        .unwrap();
+        let trade = |timestamp| Trade {
+            timestamp,
+            price: 100.0,
+            size: 1.0,
+        };
+        // The period starting at zero is not mistaken for an aggregator without any trades
+        let counts = |candles: Vec<TimeframeCandle<CountCandle>>| {
+            candles
+                .iter()
+                .map(|c| (c.period, c.candle.num_trades()))
+                .collect::<Vec<_>>()
+        };
+        assert!(a.update(&trade(0)).is_empty());
+        assert_eq!(counts(a.update(&trade(25_000))), vec![(S20, 1)]);
+        assert_eq!(counts(a.update(&trade(40_000))), vec![(S20, 1), (S40, 2)]);
+    }
+
+    #[test]
+    fn multi_timeframe_aggregator_non_mergeable_components() {
+        // The VPIN depends on the order of all trades, so it can't be merged from the smaller timeframe
+        #[derive(Debug, Default, Clone, Candle)]
+        struct VpinCandle {
+            vpin: Vpin,
+            num_trades: NumTrades<u32>,
+        }
+
+        let mut a = MultiTimeframeAggregator::<VpinCandle, Trade>::new(
+            &[S20, S40],
+            TimestampResolution::Millisecond,
+        )
+        .unwrap();
+        let got: Vec<_> = TRADES
+            .iter()
+            .flat_map(|t| a.update(t))
+            .filter(|c| c.period == S40)
+            .map(|c| c.candle)
+            .collect();
+
+        let mut aggregator = GenericAggregator::<VpinCandle, _, Trade>::new(
+            AlignedTimeRule::new(S40, TimestampResolution::Millisecond),
+            false,
+        );
+        let expected = aggregate_all_trades(&TRADES, &mut aggregator);
+        assert_eq!(got.len(), expected.len());
+        for (g, e) in got.iter().zip(expected.iter()) {
+            assert_eq!(g.num_trades(), e.num_trades());
+            assert_eq!(g.vpin().to_bits(), e.vpin().to_bits());
+        }
+        assert!(got.iter().any(|c| c.vpin().is_finite()));
+        assert!(!a.merge_candles);
+    }
+}
@@ -0,0 +1,27 @@
+//! Allows the `Candle` derive to make sure that a component named like `Open`, `High`, `Low` or `Close`
+//! is actually that component of this crate, which a derive macro can't resolve by itself from the name of a type.
+//! The derived `HighLow` and `Ohlc` implementations require these marker traits of their components,
+//! so they don't apply to candles with a type of your own of the same name,
+//! while the components may be imported or given by any path.
+
+use crate::candle_components::{Close, High, Low, Open};
+
+/// Implemented only by the `Open` component
+pub trait OpenComponent {}
+
+/// Implemented only by the `High` component
+pub trait HighComponent {}
+
+/// Implemented only by the `Low` component
+pub trait LowComponent {}
+
+/// Implemented only by the `Close` component
+pub trait CloseComponent {}
+
+impl OpenComponent for Open {}
+
+impl HighComponent for High {}
+
+impl LowComponent for Low {}
+
+impl CloseComponent for Close {}
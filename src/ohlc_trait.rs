@@ -0,0 +1,18 @@
+/// Exposes the open, high, low and close price of a candle,
+/// e.g. as the input of the `HeikinAshi` transformer.
+/// Implemented by the `Candle` macro for every candle
+/// containing the `Open`, `High`, `Low` and `Close` components,
+/// along with `HighLow`.
+pub trait Ohlc {
+    /// The price of the first trade of the candle
+    fn open(&self) -> f64;
+
+    /// The highest price of the candle
+    fn high(&self) -> f64;
+
+    /// The lowest price of the candle
+    fn low(&self) -> f64;
+
+    /// The price of the last trade of the candle
+    fn close(&self) -> f64;
+}
@@ -0,0 +1,238 @@
+use crate::{
+    renko_aggregator::LEVEL_EPSILON, BrickDirection, BrickSize, Error, Result, TakerTrade,
+};
+
+/// A column of a point and figure chart
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PointAndFigureColumn {
+    /// Up for a column of Xs, Down for a column of Os
+    pub direction: BrickDirection,
+
+    /// The grid price at which the column starts
+    pub start: f64,
+
+    /// The grid price of the most extreme box of the column
+    pub end: f64,
+
+    /// The number of boxes in the column
+    pub boxes: u32,
+
+    /// The timestamp of the trade that started the column, in the unit of the trade timestamps
+    pub start_timestamp: i64,
+
+    /// The timestamp of the trade that reached the end of the column, in the unit of the trade timestamps
+    pub end_timestamp: i64,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct Column {
+    direction: BrickDirection,
+    // The grid levels of the start and end of the column
+    start: i64,
+    end: i64,
+    start_timestamp: i64,
+    end_timestamp: i64,
+}
+
+/// Creates the columns of a point and figure chart from a stream of trades,
+/// with boxes of a fixed absolute or relative size snapped to a price grid.
+/// A column is extended with every box the price moves in its direction.
+/// Once the price moves `reversal` boxes against the column, starting from its most extreme box,
+/// the column is finished and a column in the opposite direction starts.
+/// The first column starts once the price moved one box away from the grid level closest to the first trade.
+///
+/// Trades with a price that is not on the grid, e.g. a price of zero for a relative box size, are ignored.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PointAndFigureAggregator {
+    box_size: BrickSize,
+    reversal: i64,
+    // The grid level closest to the first trade, None before the first trade
+    anchor: Option<i64>,
+    column: Option<Column>,
+}
+
+impl PointAndFigureAggregator {
+    /// Create a new instance
+    ///
+    /// # Arguments:
+    /// `box_size`: The positive absolute or relative size of each box
+    /// `reversal`: The number of boxes the price needs to move against the current column
+    ///     to start a new column, e.g. 3 for the classic three box reversal
+    ///
+    pub fn new(box_size: BrickSize, reversal: u32) -> Result<Self> {
+        if !box_size.is_valid() || reversal == 0 {
+            return Err(Error::InvalidParam);
+        }
+
+        Ok(Self {
+            box_size,
+            reversal: reversal as i64,
+            anchor: None,
+            column: None,
+        })
+    }
+
+    /// Adds a new trade to the chart
+    ///
+    /// # Arguments:
+    /// trade: the trade information to add to the aggregation process
+    ///
+    /// # Returns:
+    /// Some column only when it has been finished by a reversal,
+    /// otherwise it returns None
+    pub fn update<T: TakerTrade>(&mut self, trade: &T) -> Option<PointAndFigureColumn> {
+        // Trades that are not on the grid are ignored
+        let x = self.box_size.level_of(trade.price())?;
+        let ts = trade.timestamp();
+        let Some(anchor) = self.anchor else {
+            self.anchor = Some(x.round() as i64);
+            return None;
+        };
+        // The highest and lowest grid level the price reached
+        let up = (x + LEVEL_EPSILON).floor() as i64;
+        let down = (x - LEVEL_EPSILON).ceil() as i64;
+
+        use BrickDirection::*;
+        let Some(column) = self.column.as_mut() else {
+            let (direction, end) = match (up > anchor, down < anchor) {
+                (true, _) => (Up, up),
+                (_, true) => (Down, down),
+                _ => return None,
+            };
+            self.column = Some(Column {
+                direction,
+                start: anchor,
+                end,
+                start_timestamp: ts,
+                end_timestamp: ts,
+            });
+            return None;
+        };
+
+        let (direction, end) = match column.direction {
+            Up if up > column.end => {
+                column.end = up;
+                column.end_timestamp = ts;
+                return None;
+            }
+            Down if down < column.end => {
+                column.end = down;
+                column.end_timestamp = ts;
+                return None;
+            }
+            Up if down <= column.end - self.reversal => (Down, down),
+            Down if up >= column.end + self.reversal => (Up, up),
+            _ => return None,
+        };
+
+        let finished = Self::to_column(&self.box_size, column);
+        *column = Column {
+            direction,
+            start: column.end,
+            end,
+            start_timestamp: ts,
+            end_timestamp: ts,
+        };
+
+        Some(finished)
+    }
+
+    /// The column that is currently being extended, or None if the first column has not started yet
+    pub fn current_column(&self) -> Option<PointAndFigureColumn> {
+        self.column
+            .as_ref()
+            .map(|c| Self::to_column(&self.box_size, c))
+    }
+
+    fn to_column(box_size: &BrickSize, column: &Column) -> PointAndFigureColumn {
+        PointAndFigureColumn {
+            direction: column.direction,
+            start: box_size.price_of(column.start),
+            end: box_size.price_of(column.end),
+            boxes: column.start.abs_diff(column.end) as u32,
+            start_timestamp: column.start_timestamp,
+            end_timestamp: column.end_timestamp,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Trade;
+
+    #[test]
+    fn point_and_figure_aggregator() {
+        use BrickDirection::*;
+
+        assert!(PointAndFigureAggregator::new(BrickSize::Absolute(1.0), 0).is_err());
+        assert!(PointAndFigureAggregator::new(BrickSize::Relative(0.0), 3).is_err());
+        assert!(PointAndFigureAggregator::new(BrickSize::Relative(f64::NAN), 3).is_err());
+
+        let mut a = PointAndFigureAggregator::new(BrickSize::Absolute(1.0), 3).unwrap();
+        let prices = [100.2, 101.5, 103.0, 101.0, 100.0, 98.4, 101.9, 102.0];
+        let columns: Vec<_> = prices
+            .iter()
+            .enumerate()
+            .filter_map(|(i, price)| {
+                a.update(&Trade {
+                    timestamp: i as i64,
+                    price: *price,
+                    size: 1.0,
+                })
+            })
+            .collect();
+
+        assert_eq!(
+            columns,
+            vec![
+                PointAndFigureColumn {
+                    direction: Up,
+                    start: 100.0,
+                    end: 103.0,
+                    boxes: 3,
+                    start_timestamp: 1,
+                    end_timestamp: 2,
+                },
+                // The price had to fall three boxes below the top of the previous column
+                PointAndFigureColumn {
+                    direction: Down,
+                    start: 103.0,
+                    end: 99.0,
+                    boxes: 4,
+                    start_timestamp: 4,
+                    end_timestamp: 5,
+                },
+            ]
+        );
+        let current = a.current_column().unwrap();
+        assert_eq!(current.direction, Up);
+        assert_eq!(
+            (current.start, current.end, current.boxes),
+            (99.0, 102.0, 3)
+        );
+    }
+
+    #[test]
+    fn point_and_figure_aggregator_off_grid_prices() {
+        let mut a = PointAndFigureAggregator::new(BrickSize::Relative(0.01), 3).unwrap();
+        let mut update = |price| {
+            a.update(&Trade {
+                timestamp: 0,
+                price,
+                size: 1.0,
+            })
+        };
+        // Prices without a logarithm neither anchor the grid nor start a column
+        for price in [0.0, -1.0, f64::NAN, 100.0, 0.0, f64::INFINITY] {
+            assert!(update(price).is_none());
+        }
+        assert!(update(101.5).is_none());
+        let current = a.current_column().unwrap();
+        assert_eq!(current.direction, BrickDirection::Up);
+        assert_eq!(current.boxes, 1);
+    }
+}
@@ -0,0 +1,358 @@
+use std::marker::PhantomData;
+
+use crate::{Error, ModularCandle, Result, SyntheticTrade};
+
+// Tolerance for prices that lie exactly on a grid level, in units of bricks
+pub(crate) const LEVEL_EPSILON: f64 = 1e-9;
+
+// The number of bricks a single trade creates at most, see `RenkoAggregator`
+const MAX_BRICKS_PER_TRADE: usize = 1_000;
+
+/// The size of the bricks of a `RenkoAggregator` or the boxes of a `PointAndFigureAggregator`,
+/// which also defines the grid of prices they are snapped to.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum BrickSize {
+    /// A fixed price distance, e.g. 10.0 for bricks at 100, 110, 120, ...
+    Absolute(f64),
+
+    /// A fixed fraction of the price, e.g. 0.01 for bricks at ..., 1.01^k, 1.01^(k+1), ...
+    Relative(f64),
+}
+
+impl BrickSize {
+    /// Whether the size is positive and finite
+    pub(crate) fn is_valid(&self) -> bool {
+        let size = match self {
+            BrickSize::Absolute(size) | BrickSize::Relative(size) => *size,
+        };
+        size.is_finite() && size > 0.0
+    }
+
+    /// The continuous grid level of a price,
+    /// or None if the price is not on the grid, i.e. a price that is not finite
+    /// or a price that is not positive for a relative size, as its logarithm is not finite
+    pub(crate) fn level_of(&self, price: f64) -> Option<f64> {
+        let level = match self {
+            BrickSize::Absolute(size) => price / size,
+            BrickSize::Relative(fraction) => price.ln() / fraction.ln_1p(),
+        };
+        level.is_finite().then_some(level)
+    }
+
+    /// The price of a grid level
+    pub(crate) fn price_of(&self, level: i64) -> f64 {
+        match self {
+            BrickSize::Absolute(size) => level as f64 * size,
+            BrickSize::Relative(fraction) => (level as f64 * fraction.ln_1p()).exp(),
+        }
+    }
+}
+
+/// The direction of a renko brick, point and figure column or kagi line
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum BrickDirection {
+    /// The price rises, e.g. a column of Xs in a point and figure chart
+    Up,
+
+    /// The price falls, e.g. a column of Os in a point and figure chart
+    Down,
+}
+
+/// A finished brick of a `RenkoAggregator`
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RenkoBrick<C> {
+    /// The direction of the brick
+    pub direction: BrickDirection,
+
+    /// The grid price at which the brick opens
+    pub open: f64,
+
+    /// The grid price at which the brick closes
+    pub close: f64,
+
+    /// The number of brick sizes between the open and the close,
+    /// which is 1 unless the brick spans the levels beyond the bricks a single trade creates at most
+    pub count: u64,
+
+    /// The candle of the trades since the previous brick, including the trade that completed this brick.
+    /// If a single trade completes multiple bricks, the additional bricks don't contain any trades,
+    /// their candle is created by `ModularCandle::fill_gap` from the open and close of the brick.
+    pub candle: C,
+}
+
+/// Creates renko bricks of a fixed absolute or relative size, snapped to a price grid.
+/// A trade crossing multiple grid levels creates one brick per level,
+/// unlike the `RelativePriceRule`, which creates at most one candle per trade.
+/// As a tiny brick size or an outlier price could create millions of bricks at once,
+/// a single trade creates at most 1000 bricks, the last of which spans all remaining levels.
+///
+/// A brick in the direction of the trend is created once the price moved one brick size beyond the close of the last brick.
+/// A brick against the trend opens at the open of the last brick,
+/// so the price has to move `reversal` brick sizes beyond the close of the last brick.
+/// The first brick is created once the price moved one brick size away from the grid level closest to the first trade.
+///
+/// Trades with a price that is not on the grid, e.g. a price of zero for a relative brick size, are ignored.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RenkoAggregator<C, T> {
+    brick_size: BrickSize,
+    reversal: i64,
+    // The grid level of the close of the last brick,
+    // or the level closest to the first trade until the first brick, None before the first trade
+    level: Option<i64>,
+    // The direction of the last brick
+    trend: Option<BrickDirection>,
+    candle: C,
+    _trade_type: PhantomData<T>,
+}
+
+impl<C, T> RenkoAggregator<C, T>
+where
+    C: ModularCandle<T>,
+    T: SyntheticTrade,
+{
+    /// Create a new instance
+    ///
+    /// # Arguments:
+    /// `brick_size`: The positive absolute or relative size of each brick
+    /// `reversal`: The number of brick sizes the price needs to move against the trend
+    ///     to create a brick in the opposite direction, at least 2. The classic renko chart uses 2.
+    ///
+    pub fn new(brick_size: BrickSize, reversal: u32) -> Result<Self> {
+        if !brick_size.is_valid() || reversal < 2 {
+            return Err(Error::InvalidParam);
+        }
+
+        Ok(Self {
+            brick_size,
+            reversal: reversal as i64,
+            level: None,
+            trend: None,
+            candle: Default::default(),
+            _trade_type: PhantomData,
+        })
+    }
+
+    /// Adds a new trade to the aggregation
+    ///
+    /// # Arguments:
+    /// trade: the trade information to add to the aggregation process
+    ///
+    /// # Returns:
+    /// The bricks completed by this trade, in chronological order.
+    /// Empty if no brick has been completed.
+    pub fn update(&mut self, trade: &T) -> Vec<RenkoBrick<C>> {
+        let Some(x) = self.brick_size.level_of(trade.price()) else {
+            return vec![];
+        };
+        self.candle.update(trade);
+
+        let Some(level) = self.level else {
+            self.level = Some(x.round() as i64);
+            return vec![];
+        };
+        // The highest and lowest grid level the price reached
+        let up = (x + LEVEL_EPSILON).floor() as i64;
+        let down = (x - LEVEL_EPSILON).ceil() as i64;
+
+        use BrickDirection::*;
+        // The open of the first brick and the close of the last brick
+        let (direction, from, to) = match self.trend {
+            None | Some(Up) if up > level => (Up, level, up),
+            None | Some(Down) if down < level => (Down, level, down),
+            Some(Up) if down <= level.saturating_sub(self.reversal) => (Down, level - 1, down),
+            Some(Down) if up >= level.saturating_add(self.reversal) => (Up, level + 1, up),
+            _ => return vec![],
+        };
+        let step = if direction == Up { 1 } else { -1 };
+
+        let mut out = Vec::with_capacity((from.abs_diff(to) as usize).min(MAX_BRICKS_PER_TRADE));
+        let mut open = from;
+        while open != to {
+            // The last brick spans all remaining levels
+            let close = if out.len() + 1 == MAX_BRICKS_PER_TRADE {
+                to
+            } else {
+                open + step
+            };
+            let open_price = self.brick_size.price_of(open);
+            let close_price = self.brick_size.price_of(close);
+            let candle = if out.is_empty() {
+                self.candle.clone()
+            } else {
+                let mut candle = C::default();
+                candle.fill_gap(
+                    &T::synthetic(trade.timestamp(), open_price),
+                    &T::synthetic(trade.timestamp(), close_price),
+                );
+                candle
+            };
+            out.push(RenkoBrick {
+                direction,
+                open: open_price,
+                close: close_price,
+                count: open.abs_diff(close),
+                candle,
+            });
+            open = close;
+        }
+        self.candle.reset();
+        self.level = Some(to);
+        self.trend = Some(direction);
+
+        out
+    }
+
+    /// Get a reference to the candle of the trades since the last brick
+    pub fn unfinished_candle(&self) -> &C {
+        &self.candle
+    }
+
+    /// The direction of the last brick, or None if no brick has been created yet
+    pub fn trend(&self) -> Option<BrickDirection> {
+        self.trend
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{plot::OhlcCandle, Trade};
+
+    fn trade(price: f64) -> Trade {
+        Trade {
+            timestamp: 1_684_677_200_000,
+            price,
+            size: 1.0,
+        }
+    }
+
+    fn bricks(
+        a: &mut RenkoAggregator<OhlcCandle, Trade>,
+        price: f64,
+    ) -> Vec<(BrickDirection, f64, f64)> {
+        a.update(&trade(price))
+            .iter()
+            .map(|b| (b.direction, b.open, b.close))
+            .collect()
+    }
+
+    #[test]
+    fn renko_aggregator_invalid_params() {
+        assert!(RenkoAggregator::<OhlcCandle, Trade>::new(BrickSize::Absolute(0.0), 2).is_err());
+        assert!(RenkoAggregator::<OhlcCandle, Trade>::new(BrickSize::Relative(-0.01), 2).is_err());
+        assert!(RenkoAggregator::<OhlcCandle, Trade>::new(BrickSize::Absolute(1.0), 1).is_err());
+        assert!(
+            RenkoAggregator::<OhlcCandle, Trade>::new(BrickSize::Absolute(f64::NAN), 2).is_err()
+        );
+    }
+
+    #[test]
+    fn renko_aggregator_off_grid_prices() {
+        use BrickDirection::*;
+
+        let mut a =
+            RenkoAggregator::<OhlcCandle, Trade>::new(BrickSize::Relative(0.01), 2).unwrap();
+        // Prices without a logarithm are ignored, instead of reaching a level at infinity
+        assert!(bricks(&mut a, 0.0).is_empty());
+        assert!(bricks(&mut a, 100.0).is_empty());
+        for price in [0.0, -100.0, f64::NAN, f64::INFINITY] {
+            assert!(bricks(&mut a, price).is_empty());
+        }
+        assert_eq!(a.unfinished_candle().open(), 100.0);
+        assert_eq!(a.unfinished_candle().close(), 100.0);
+
+        let out = bricks(&mut a, 101.5);
+        assert_eq!(out.len(), 1);
+        assert_eq!(out[0].0, Up);
+    }
+
+    #[test]
+    fn renko_aggregator() {
+        use BrickDirection::*;
+
+        let mut a = RenkoAggregator::<OhlcCandle, Trade>::new(BrickSize::Absolute(1.0), 2).unwrap();
+        // The grid level closest to the first trade is 100
+        assert!(bricks(&mut a, 100.2).is_empty());
+        assert!(bricks(&mut a, 100.9).is_empty());
+        assert_eq!(a.trend(), None);
+
+        // A gap of three brick sizes creates three bricks
+        let out = a.update(&trade(103.4));
+        assert_eq!(out.len(), 3);
+        assert_eq!(out[0].candle.open(), 100.2);
+        assert_eq!(out[0].candle.close(), 103.4);
+        // The additional bricks are filled from their open and close
+        assert_eq!(out[1].candle.open(), 101.0);
+        assert_eq!(out[1].candle.high(), 102.0);
+        assert_eq!(out[1].candle.low(), 101.0);
+        assert_eq!(out[2].candle.close(), 103.0);
+        assert!(out.iter().all(|b| b.count == 1));
+        assert_eq!(
+            out.iter()
+                .map(|b| (b.direction, b.open, b.close))
+                .collect::<Vec<_>>(),
+            vec![(Up, 100.0, 101.0), (Up, 101.0, 102.0), (Up, 102.0, 103.0)]
+        );
+
+        // The reversal needs two brick sizes below the last close
+        assert!(bricks(&mut a, 102.5).is_empty());
+        assert_eq!(bricks(&mut a, 101.0), vec![(Down, 102.0, 101.0)]);
+        assert!(bricks(&mut a, 100.5).is_empty());
+        assert_eq!(a.unfinished_candle().open(), 100.5);
+        assert_eq!(
+            bricks(&mut a, 99.0),
+            vec![(Down, 101.0, 100.0), (Down, 100.0, 99.0)]
+        );
+        assert!(bricks(&mut a, 100.9).is_empty());
+        assert_eq!(bricks(&mut a, 101.0), vec![(Up, 100.0, 101.0)]);
+        assert_eq!(a.trend(), Some(Up));
+    }
+
+    #[test]
+    fn renko_aggregator_grid() {
+        // Prices exactly on a grid level complete a brick
+        let mut a = RenkoAggregator::<OhlcCandle, Trade>::new(BrickSize::Absolute(0.1), 2).unwrap();
+        assert!(a.update(&trade(100.0)).is_empty());
+        assert_eq!(a.update(&trade(100.3)).len(), 3);
+
+        // Relative bricks of 1% at powers of 1.01
+        let mut a =
+            RenkoAggregator::<OhlcCandle, Trade>::new(BrickSize::Relative(0.01), 3).unwrap();
+        assert!(a.update(&trade(100.0)).is_empty());
+        let out = a.update(&trade(103.0));
+        assert_eq!(out.len(), 2);
+        for (b, level) in out.iter().zip([464, 465]) {
+            assert!((b.close - 1.01_f64.powi(level)).abs() < 1e-9);
+            assert!((b.close / b.open - 1.01).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn renko_aggregator_max_bricks_per_trade() {
+        // A gap of 100 million brick sizes
+        let mut a =
+            RenkoAggregator::<OhlcCandle, Trade>::new(BrickSize::Absolute(1e-6), 2).unwrap();
+        assert!(a.update(&trade(100.0)).is_empty());
+        let out = a.update(&trade(200.0));
+        assert_eq!(out.len(), MAX_BRICKS_PER_TRADE);
+        assert!(out[..MAX_BRICKS_PER_TRADE - 1].iter().all(|b| b.count == 1));
+
+        // The last brick spans all remaining levels
+        let last = out.last().unwrap();
+        assert_eq!(last.count, 100_000_000 - 999);
+        assert!((last.open - 100.000_999).abs() < 1e-9);
+        assert!((last.close - 200.0).abs() < 1e-9);
+        assert_eq!(last.candle.open(), last.open);
+        assert_eq!(last.candle.high(), last.close);
+
+        // The grid continues from the level the trade reached
+        let out = a.update(&trade(200.000_001));
+        assert_eq!(out.len(), 1);
+        assert_eq!(out[0].count, 1);
+        assert!((out[0].open - 200.0).abs() < 1e-9);
+    }
+}
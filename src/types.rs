@@ -1,3 +1,5 @@
+use crate::{Error, Result};
+
 #[derive(Default, Debug, Clone, Copy, PartialEq)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 /// Defines a taker trade
@@ -34,6 +36,8 @@ impl TakerTrade for Trade {
 /// either by Base currency or Quote Currency
 /// assumes trades sizes are denoted in Quote
 /// e.g.: buy 10 contracts of BTC would be trade size of 10
+/// Dividing by the price is only correct for inverse contracts with a multiplier of 1,
+/// use `Notional` to follow the `ContractSpec` of the trades for any contract type.
 #[derive(Debug, Clone, Copy)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum By {
@@ -42,6 +46,70 @@ pub enum By {
     /// when aggregating by Quote, take the raw trade size for volume sum
     /// as the assumption is that Trade size is denoted in Quote
     Quote,
+    /// when aggregating by Notional, take the notional value of the trade for volume sum,
+    /// as defined by the `ContractSpec` of the trade
+    Notional,
+}
+
+impl By {
+    /// The volume of a trade, or a part of it, which is always positive
+    ///
+    /// # Arguments:
+    /// trade: The trade, providing the price and contract specification
+    /// size: The size of the trade, or the part of it, in contracts
+    ///
+    #[inline(always)]
+    pub fn volume<T: TakerTrade>(&self, trade: &T, size: f64) -> f64 {
+        match self {
+            By::Base => size.abs() / trade.price(),
+            By::Quote => size.abs(),
+            By::Notional => trade.contract_spec().notional(trade.price(), size),
+        }
+    }
+}
+
+/// The contract specification of an instrument,
+/// defining how the size and price of a trade translate into traded notional value.
+/// The multiplier is the value of one contract, e.g. 1.0 for spot.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ContractSpec {
+    /// The size is denoted in the base currency, e.g. spot or linear perpetuals.
+    /// The notional is `size * price * multiplier`, denoted in the quote currency.
+    Linear {
+        /// The amount of base currency per contract
+        multiplier: f64,
+    },
+    /// The size is denoted in the quote currency, e.g. XBTUSD on Bitmex.
+    /// The notional is `size * multiplier / price`, denoted in the base currency.
+    Inverse {
+        /// The amount of quote currency per contract
+        multiplier: f64,
+    },
+    /// The payout is in a currency other than the quote currency, e.g. ETHUSD on Bitmex which settles in XBT.
+    /// The notional is `size * price * multiplier`, denoted in the settlement currency.
+    Quanto {
+        /// The amount of settlement currency per contract and unit of price
+        multiplier: f64,
+    },
+}
+
+impl ContractSpec {
+    /// The notional value of a trade, which is always positive
+    ///
+    /// # Arguments:
+    /// price: The price of the trade
+    /// size: The size of the trade, in contracts
+    ///
+    #[inline(always)]
+    pub fn notional(&self, price: f64, size: f64) -> f64 {
+        match self {
+            ContractSpec::Linear { multiplier } | ContractSpec::Quanto { multiplier } => {
+                size.abs() * price * multiplier
+            }
+            ContractSpec::Inverse { multiplier } => size.abs() * multiplier / price,
+        }
+    }
 }
 
 /// Trait to enable third party types to be passed into aggregators.
@@ -55,6 +123,13 @@ pub trait TakerTrade {
         TimestampResolution::Millisecond
     }
 
+    /// The contract specification of the traded instrument,
+    /// used for the notional value of a trade, e.g. by the `VolumeRule` with `By::Notional`.
+    /// A default implementation is included and assumes a linear instrument with a multiplier of 1
+    fn contract_spec(&self) -> ContractSpec {
+        ContractSpec::Linear { multiplier: 1.0 }
+    }
+
     /// Fill price of the transaction
     fn price(&self) -> f64;
 
@@ -64,9 +139,45 @@ pub trait TakerTrade {
     fn size(&self) -> f64;
 }
 
+/// Allows an aggregator to create trades that did not happen,
+/// e.g. to fill periods without any trades with synthetic candles.
+pub trait SyntheticTrade: TakerTrade {
+    /// Create a trade of zero size at the given timestamp and price
+    fn synthetic(timestamp: i64, price: f64) -> Self;
+}
+
+impl SyntheticTrade for Trade {
+    fn synthetic(timestamp: i64, price: f64) -> Self {
+        Self {
+            timestamp,
+            price,
+            size: 0.0,
+        }
+    }
+}
+
+/// Allows an aggregator to split a trade into multiple parts,
+/// e.g. to distribute a large trade over multiple volume buckets.
+pub trait SplittableTrade: TakerTrade {
+    /// Create a copy of the trade with a different size.
+    /// The size carries the same sign as the size of the original trade.
+    fn with_size(&self, size: f64) -> Self;
+}
+
+impl SplittableTrade for Trade {
+    fn with_size(&self, size: f64) -> Self {
+        Self { size, ..*self }
+    }
+}
+
 /// The resolution of the "TakerTrade" timestamps
 #[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum TimestampResolution {
+    /// The timestamp of the TakerTrade is measured in seconds.
+    /// Periods have to be a whole number of seconds.
+    Second,
+
     /// The timestamp of the TakerTrade is measured in milliseconds
     Millisecond,
 
@@ -77,8 +188,80 @@ pub enum TimestampResolution {
     Nanosecond,
 }
 
+impl TimestampResolution {
+    /// The number of timestamp units in one second
+    pub(crate) fn units_per_second(self) -> i64 {
+        use TimestampResolution::*;
+        match self {
+            Second => 1,
+            Millisecond => 1_000,
+            Microsecond => 1_000_000,
+            Nanosecond => 1_000_000_000,
+        }
+    }
+
+    /// Converts a period into the unit of the timestamps,
+    /// or returns an error if the period can't be expressed in that unit,
+    /// i.e. a period that is not a whole number of seconds for the `Second` resolution
+    pub(crate) fn period_in_units(self, period: MillisecondPeriod) -> Result<i64> {
+        use TimestampResolution::*;
+        // Given the timestamp resolution of the trades, a certain multiplier is required to compute the number of units for the sample period.
+        Ok(match self {
+            Second => {
+                if period.get() % 1_000 != 0 {
+                    return Err(Error::InvalidParam);
+                }
+                period.get() as i64 / 1_000
+            }
+            Millisecond => period.get() as i64,
+            Microsecond => period.get() as i64 * 1_000,
+            Nanosecond => period.get() as i64 * 1_000_000,
+        })
+    }
+
+    /// Converts a timestamp of this resolution into a `DateTime<Utc>`,
+    /// or None if the timestamp is outside of the range supported by chrono.
+    #[cfg(feature = "chrono")]
+    pub(crate) fn datetime_from_timestamp(
+        self,
+        timestamp: i64,
+    ) -> Option<chrono::DateTime<chrono::Utc>> {
+        use chrono::DateTime;
+        use TimestampResolution::*;
+        match self {
+            Second => DateTime::from_timestamp(timestamp, 0),
+            Millisecond => DateTime::from_timestamp_millis(timestamp),
+            Microsecond => DateTime::from_timestamp_micros(timestamp),
+            Nanosecond => Some(DateTime::from_timestamp_nanos(timestamp)),
+        }
+    }
+
+    /// Converts a `DateTime` into a timestamp of this resolution.
+    /// Date times outside of the range of nanosecond timestamps saturate.
+    #[cfg(feature = "chrono")]
+    pub(crate) fn timestamp_from_datetime<Tz: chrono::TimeZone>(
+        self,
+        datetime: &chrono::DateTime<Tz>,
+    ) -> i64 {
+        use TimestampResolution::*;
+        match self {
+            Second => datetime.timestamp(),
+            Millisecond => datetime.timestamp_millis(),
+            Microsecond => datetime.timestamp_micros(),
+            Nanosecond => datetime
+                .timestamp_nanos_opt()
+                .unwrap_or(if datetime.timestamp() < 0 {
+                    i64::MIN
+                } else {
+                    i64::MAX
+                }),
+        }
+    }
+}
+
 /// A period measured in milliseconds which must be non-zero.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct MillisecondPeriod(u64);
 
 impl MillisecondPeriod {
@@ -127,4 +310,46 @@ mod test {
             MillisecondPeriod(60_000)
         );
     }
+
+    #[test]
+    fn timestamp_resolution_period_in_units() {
+        use TimestampResolution::*;
+        let period = MillisecondPeriod::from_non_zero_secs(90);
+        assert_eq!(Second.period_in_units(period).unwrap(), 90);
+        assert_eq!(Nanosecond.period_in_units(period).unwrap(), 90_000_000_000);
+        // Sub second periods would have to be rounded
+        for millis in [500, 1_500] {
+            let period = MillisecondPeriod::from_non_zero(millis);
+            assert!(matches!(
+                Second.period_in_units(period),
+                Err(Error::InvalidParam)
+            ));
+            assert_eq!(
+                Microsecond.period_in_units(period).unwrap(),
+                millis as i64 * 1_000
+            );
+        }
+    }
+
+    #[cfg(feature = "chrono")]
+    #[test]
+    fn timestamp_resolution_datetime() {
+        use chrono::{TimeZone, Utc};
+
+        let expected = Utc.with_ymd_and_hms(2023, 5, 21, 13, 53, 20).unwrap();
+        let secs = 1_684_677_200;
+        use TimestampResolution::*;
+        for (res, ts) in [
+            (Second, secs),
+            (Millisecond, secs * 1_000),
+            (Microsecond, secs * 1_000_000),
+            (Nanosecond, secs * 1_000_000_000),
+        ] {
+            assert_eq!(res.datetime_from_timestamp(ts), Some(expected));
+            assert_eq!(res.timestamp_from_datetime(&expected), ts);
+        }
+        // Far beyond the year 262143, the last year supported by chrono
+        assert_eq!(Second.datetime_from_timestamp(i64::MAX), None);
+        assert_eq!(Millisecond.datetime_from_timestamp(i64::MIN), None);
+    }
 }
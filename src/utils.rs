@@ -17,6 +17,7 @@ use crate::{errors::Result, Aggregator, ModularCandle, TakerTrade, Trade};
 /// 10 days of 1 hour candles -> 240 candles
 /// assuming 9840 volume traded over 10 days
 /// -> each candle should have 41 volume to produce 240 candles using volume aggregation
+/// See `AdaptiveVolumeRule` for an online form, which keeps adapting the candle volume
 pub fn candle_volume_from_time_period(
     total_volume: f64,
     total_time_days: f64,
@@ -51,6 +52,29 @@ where
     out
 }
 
+/// Apply an aggregator for all trades at once,
+/// including the last unfinished candle, see `Aggregator::flush`
+///
+/// # Arguments:
+/// trades: The input trade data to aggregate
+/// aggregator: Something that can aggregate
+///
+/// # Returns:
+/// A vector of aggregated candle data, where the last candle may be partial
+pub fn aggregate_all_trades_and_flush<A, C, T>(trades: &[T], aggregator: &mut A) -> Vec<C>
+where
+    A: Aggregator<C, T>,
+    C: ModularCandle<T>,
+    T: TakerTrade,
+{
+    let mut out = aggregate_all_trades(trades, aggregator);
+    if let Some(candle) = aggregator.flush() {
+        out.push(candle)
+    }
+
+    out
+}
+
 /// Load trades from csv file
 ///
 /// # Arguments:
@@ -86,8 +110,17 @@ pub fn load_trades_from_csv(filename: &str) -> Result<Vec<Trade>> {
 #[cfg(test)]
 mod tests {
     use round::round;
+    use trade_aggregation_derive::Candle;
 
     use super::*;
+    use crate::{
+        candle_components::NumTrades, GenericAggregator, TimeRule, TimestampResolution, M1,
+    };
+
+    #[derive(Default, Debug, Clone, Candle)]
+    struct MyCandle {
+        num_trades: NumTrades<u32>,
+    }
 
     // TODO: re-enable this test
     /*
@@ -100,6 +133,22 @@ mod tests {
     }
     */
 
+    #[test]
+    fn test_aggregate_all_trades_and_flush() {
+        let trades = load_trades_from_csv("data/Bitstamp_BTCEUR_1M.csv").unwrap();
+
+        let rule = TimeRule::new(M1, TimestampResolution::Microsecond);
+        let mut aggregator = GenericAggregator::<MyCandle, _, Trade>::new(rule.clone(), false);
+        let candles = aggregate_all_trades(&trades, &mut aggregator);
+        assert_eq!(candles.len(), 1);
+
+        let mut aggregator = GenericAggregator::<MyCandle, _, Trade>::new(rule, false);
+        let candles = aggregate_all_trades_and_flush(&trades, &mut aggregator);
+        assert_eq!(candles.len(), 2);
+        assert_eq!(candles[0].num_trades(), 10);
+        assert_eq!(candles[1].num_trades(), 1);
+    }
+
     #[test]
     fn test_candle_volume_from_time_period() {
         let total_volume = 100.0;
@@ -44,6 +44,20 @@ impl WelfordOnline {
         self.mean += (val - old_mean) / self.count as f64;
         self.s += (val - old_mean) * (val - self.mean);
     }
+
+    // merge combines the statistics of two disjoint sets of values,
+    // using the parallel algorithm of Chan et al.
+    pub fn merge(&mut self, other: &Self) {
+        let count = self.count + other.count;
+        if count == 0 {
+            return;
+        }
+        let delta = other.mean - self.mean;
+        let weight = other.count as f64 / count as f64;
+        self.mean += delta * weight;
+        self.s += other.s + delta * delta * self.count as f64 * weight;
+        self.count = count;
+    }
 }
 
 #[cfg(test)]
@@ -63,4 +77,24 @@ mod tests {
         }
         assert_eq!(round(welford.std_dev(), 4), 0.5774);
     }
+
+    #[test]
+    fn welford_online_merge() {
+        let vals = [1.0, 2.0, 4.0, 7.0, 11.0];
+        let mut all = WelfordOnline::new();
+        for v in &vals {
+            all.add(*v);
+        }
+
+        for split in 0..=vals.len() {
+            let mut first = WelfordOnline::new();
+            let mut second = WelfordOnline::new();
+            vals[..split].iter().for_each(|v| first.add(*v));
+            vals[split..].iter().for_each(|v| second.add(*v));
+            first.merge(&second);
+            assert_eq!(first.count, all.count);
+            assert_eq!(round(first.mean, 10), round(all.mean, 10));
+            assert_eq!(round(first.variance(), 10), round(all.variance(), 10));
+        }
+    }
 }
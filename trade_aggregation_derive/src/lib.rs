@@ -9,19 +9,34 @@
 //!    open: Open,
 //! }
 //! with the derive macro will create a "fn open(&self)" method which gets the inner value
+//! The value type is 'f64', unless the component names it as its type parameter,
+//! e.g. 'NumTrades<u32>' or 'OpenDateTime<DateTime<Utc>>'.
 //!
-//! When deriving the 'Candle' macro, make sure the following things are in scope:
-//! - Trade
-//! - ModularCandle
-//! - CandleComponent
+//! The generated code refers to the traits and types of the 'trade_aggregation' crate
+//! by their absolute paths, e.g. '::trade_aggregation::ModularCandle',
+//! so none of them need to be in scope when deriving the 'Candle' macro.
+//! Only the 'input' type, if given, is used as written.
+//! Within 'trade_aggregation' itself, these paths resolve through 'extern crate self as trade_aggregation'.
+//!
+//! If every 'CandleComponent' of the struct implements 'MergeableComponent',
+//! 'ModularCandle::try_merge' combines a subsequent candle into this one,
+//! while it does nothing for the others,
+//! which it tells apart with the hidden 'trade_aggregation::merge_probe' module.
+//!
+//! If the struct contains 'High' and 'Low' components, the 'HighLow' trait is implemented as well,
+//! e.g. for the 'RangeRule'. If it also contains 'Open' and 'Close' components,
+//! the 'Ohlc' trait is implemented too.
+//! As a derive macro can't resolve types, these components are recognized by their type name,
+//! e.g. 'High' or 'candle_components::High', and the traits are only implemented
+//! if they are the components of 'trade_aggregation', as required through the hidden
+//! 'trade_aggregation::ohlc_markers' module. So a type of your own named 'High' is never mistaken for it.
 
 #![deny(missing_docs)]
 
 use proc_macro::TokenStream;
-use quote::{__private::Span, quote};
+use quote::quote;
 use syn::{
-    self, AngleBracketedGenericArguments, Data, DataStruct, Fields, GenericArgument, Ident, Type,
-    TypePath,
+    self, AngleBracketedGenericArguments, Data, DataStruct, Fields, GenericArgument, Type, TypePath,
 };
 
 /// The 'Candle' macro takes a named struct,
@@ -29,7 +44,9 @@ use syn::{
 /// to automatically generate a struct that implements
 /// the 'ModularCandle' trait, which means it can then be used
 /// in the aggregation process.
-/// It also exposes getter functions for each 'CandleComponent' for convenience.
+/// It also exposes getter functions for each 'CandleComponent' for convenience,
+/// as well as the 'HighLow' trait, if it contains 'High' and 'Low' components,
+/// and the 'Ohlc' trait, if it contains 'Open', 'High', 'Low' and 'Close' components.
 #[proc_macro_derive(Candle)]
 pub fn candle_macro_derive(input: TokenStream) -> TokenStream {
     // Construct a representation of Rust code as a syntax tree
@@ -40,19 +57,12 @@ pub fn candle_macro_derive(input: TokenStream) -> TokenStream {
     impl_candle_macro(&ast)
 }
 
-fn phantom_path_to_type(path: &syn::Path) -> Option<Ident> {
-    if path.leading_colon.is_some() {
-        return None;
-    }
-    let mut it = path.segments.iter();
-    let segment = it.next()?;
-    match &segment.arguments {
+/// The type argument of the last path segment, e.g. 'u32' for 'candle_components::NumTrades<u32>'
+fn phantom_path_to_type(path: &syn::Path) -> Option<TypePath> {
+    match &path.segments.last()?.arguments {
         syn::PathArguments::AngleBracketed(AngleBracketedGenericArguments { args: x, .. }) => {
             match x.first() {
-                Some(GenericArgument::Type(Type::Path(TypePath {
-                    path: syn::Path { segments: segs, .. },
-                    ..
-                }))) => segs.first().and_then(|x| Some(x.ident.clone())),
+                Some(GenericArgument::Type(Type::Path(type_path))) => Some(type_path.clone()),
                 _ => None,
             }
         }
@@ -60,6 +70,19 @@ fn phantom_path_to_type(path: &syn::Path) -> Option<Ident> {
     }
 }
 
+/// The index of the 'Open', 'High', 'Low' or 'Close' component, in that order,
+/// if the last segment of the path is named like one of them.
+/// Whether it actually is that component is checked by the bounds of the generated impls.
+fn ohlc_index(path: &syn::Path) -> Option<usize> {
+    match path.segments.last()?.ident.to_string().as_str() {
+        "Open" => Some(0),
+        "High" => Some(1),
+        "Low" => Some(2),
+        "Close" => Some(3),
+        _ => None,
+    }
+}
+
 fn impl_candle_macro(ast: &syn::DeriveInput) -> TokenStream {
     let name = &ast.ident;
     let components = match &ast.data {
@@ -70,10 +93,13 @@ fn impl_candle_macro(ast: &syn::DeriveInput) -> TokenStream {
         _ => panic!("Use a named struct"),
     };
 
-    let default_output_type = Ident::new("f64", Span::call_site());
-    let mut input_type = Some(Ident::new("Trade", Span::call_site()));
+    let default_output_type: TypePath = syn::parse_quote! { f64 };
+    let mut input_type: Option<TypePath> = Some(syn::parse_quote! { ::trade_aggregation::Trade });
     let mut value_idents = vec![];
     let mut value_types = vec![];
+    let mut component_types = vec![];
+    // The fields of the 'Open', 'High', 'Low' and 'Close' components, in that order
+    let mut ohlc_fields = [None, None, None, None];
 
     for c in components {
         if let syn::Field {
@@ -83,9 +109,13 @@ fn impl_candle_macro(ast: &syn::DeriveInput) -> TokenStream {
         } = c
         {
             if ident.clone().unwrap().to_string().as_str() == "input" {
-                input_type = phantom_path_to_type(&p);
+                input_type = phantom_path_to_type(p);
             } else {
-                if let Some(type_) = phantom_path_to_type(&p) {
+                component_types.push(c.ty.clone());
+                if let Some(i) = ohlc_index(p) {
+                    ohlc_fields[i] = ident.clone().map(|ident| (ident, c.ty.clone()));
+                }
+                if let Some(type_) = phantom_path_to_type(p) {
                     value_idents.push(ident);
                     value_types.push(type_);
                 } else {
@@ -99,31 +129,116 @@ fn impl_candle_macro(ast: &syn::DeriveInput) -> TokenStream {
     let fn_names0 = value_idents.clone();
     let fn_names1 = fn_names0.clone();
     let fn_names2 = fn_names1.clone();
+    let fn_names3 = fn_names2.clone();
     let input_name = input_type.expect("No PhantomData for input attribute type!");
 
+    // The higher ranked bounds defer the check to the use of the impl,
+    // so candles with a type of your own named like a component still compile, without the impl.
+    let high_low_impl = match &ohlc_fields {
+        [_, Some((high, high_type)), Some((low, low_type)), _] => quote! {
+            impl ::trade_aggregation::HighLow for #name
+            where
+                for<'a> #high_type: ::trade_aggregation::ohlc_markers::HighComponent,
+                for<'a> #low_type: ::trade_aggregation::ohlc_markers::LowComponent,
+            {
+                fn highest(&self) -> f64 {
+                    ::trade_aggregation::CandleComponent::<f64>::value(&self.#high)
+                }
+
+                fn lowest(&self) -> f64 {
+                    ::trade_aggregation::CandleComponent::<f64>::value(&self.#low)
+                }
+            }
+        },
+        _ => quote! {},
+    };
+
+    let ohlc_impl = match ohlc_fields {
+        [Some((open, open_type)), Some((high, high_type)), Some((low, low_type)), Some((close, close_type))] =>
+        {
+            quote! {
+                impl ::trade_aggregation::Ohlc for #name
+                where
+                    for<'a> #open_type: ::trade_aggregation::ohlc_markers::OpenComponent,
+                    for<'a> #high_type: ::trade_aggregation::ohlc_markers::HighComponent,
+                    for<'a> #low_type: ::trade_aggregation::ohlc_markers::LowComponent,
+                    for<'a> #close_type: ::trade_aggregation::ohlc_markers::CloseComponent,
+                {
+                    fn open(&self) -> f64 {
+                        ::trade_aggregation::CandleComponent::<f64>::value(&self.#open)
+                    }
+
+                    fn high(&self) -> f64 {
+                        ::trade_aggregation::CandleComponent::<f64>::value(&self.#high)
+                    }
+
+                    fn low(&self) -> f64 {
+                        ::trade_aggregation::CandleComponent::<f64>::value(&self.#low)
+                    }
+
+                    fn close(&self) -> f64 {
+                        ::trade_aggregation::CandleComponent::<f64>::value(&self.#close)
+                    }
+                }
+            }
+        }
+        _ => quote! {},
+    };
+
     let gen = quote! {
         impl #name {
             #(
                 /// Get the value of this candle component.
                 pub fn #fn_names0(&self) -> #value_types {
-                    self.#fn_names0.value()
+                    ::trade_aggregation::CandleComponent::<#value_types>::value(&self.#fn_names0)
                 }
             )*
         }
 
-        impl ModularCandle<#input_name> for #name {
+        impl ::trade_aggregation::ModularCandle<#input_name> for #name {
             fn update(&mut self, trade: &#input_name) {
                 #(
-                    self.#fn_names1.update(trade);
+                    ::trade_aggregation::CandleComponentUpdate::<#input_name>::update(&mut self.#fn_names1, trade);
                 )*
             }
 
             fn reset(&mut self) {
                 #(
-                    self.#fn_names2.reset();
+                    ::trade_aggregation::CandleComponent::<#value_types>::reset(&mut self.#fn_names2);
+                )*
+            }
+
+            fn try_merge(&mut self, other: &Self) -> bool {
+                use ::trade_aggregation::merge_probe::{Mergeable as _, NotMergeable as _};
+                let mergeable = true #(
+                    && (&::trade_aggregation::merge_probe::MergeProbe::<#component_types>::new()).is_mergeable()
+                )*;
+                if !mergeable {
+                    return false;
+                }
+                #(
+                    (&::trade_aggregation::merge_probe::MergeProbe::<#component_types>::new())
+                        .merge(&mut self.#fn_names3, &other.#fn_names3);
+                )*
+                true
+            }
+
+            fn carry_over(&mut self, trade: &#input_name) {
+                #(
+                    ::trade_aggregation::CandleComponentUpdate::<#input_name>::carry_over(&mut self.#fn_names1, trade);
+                )*
+            }
+
+            fn fill_gap(&mut self, open: &#input_name, close: &#input_name) {
+                #(
+                    ::trade_aggregation::CandleComponentUpdate::<#input_name>::fill_gap(&mut self.#fn_names1, open, close);
                 )*
             }
         }
+
+        #high_low_impl
+
+        #ohlc_impl
     };
 
     gen.into()